@@ -0,0 +1,493 @@
+//! Derive macros for the `asg` models.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, punctuated::Punctuated, Data, DeriveInput, Error, Fields, GenericArgument,
+    LitStr, Meta, PathArguments, Token, Type,
+};
+
+/// Derives `ModelRepository` for a model struct.
+///
+/// Struct attribute (`table` and `create` are required):
+///
+/// ```text
+/// #[model(table = "customers", create = CustomerForCreate, update = CustomerForUpdate)]
+/// ```
+///
+/// Without `update`, the model is updated through a bare `Changeset`. Adding
+/// `unaudited` keeps the model's writes out of the audit log, and
+/// `search_vector = "column"` names a stored `tsvector` over the search
+/// columns for full-text search.
+///
+/// Field attributes, under `#[model(...)]`:
+///
+/// - `create` / `update`: the column is written on insert / update. The create
+///   and update structs must have a field of the same name; create fields are
+///   bound in declaration order, and the update struct's fields are `Patch<T>`.
+/// - `search`: included in the free-text `q` search.
+/// - `sortable`, `filterable`, `indexed` (both): exposed to list queries. The
+///   column kind and nullability are inferred from the field type.
+/// - `archive`: the soft-delete timestamp column.
+/// - `version`: the column the row's ETag is derived from, usually `updated_at`.
+/// - `skip`: ignored entirely.
+///
+/// `FIELDS` lists every field the model serializes, in declaration order,
+/// whatever its `#[model(...)]` attributes; fields under `#[serde(skip)]` or
+/// `#[serde(skip_serializing)]` are left out.
+#[proc_macro_derive(ModelRepository, attributes(model))]
+pub fn derive_model_repository(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    create: bool,
+    update: bool,
+    search: bool,
+    sortable: bool,
+    filterable: bool,
+    archive: bool,
+    version: bool,
+    skip: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let mut table: Option<LitStr> = None;
+    let mut create: Option<syn::Path> = None;
+    let mut update: Option<syn::Path> = None;
+    let mut unaudited = false;
+    let mut search_vector: Option<LitStr> = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("model"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("create") {
+                create = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("update") {
+                update = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("unaudited") {
+                unaudited = true;
+            } else if meta.path.is_ident("search_vector") {
+                search_vector = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error(
+                    "expected `table`, `create`, `update`, `unaudited` or `search_vector`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    let missing = |name: &str| {
+        Error::new_spanned(
+            ident,
+            format!("missing `#[model({} = ...)]` attribute", name),
+        )
+    };
+    let table = table.ok_or_else(|| missing("table"))?;
+    let create = create.ok_or_else(|| missing("create"))?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    ident,
+                    "expected a struct with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new_spanned(ident, "expected a struct")),
+    };
+
+    let mut create_fields = Vec::new();
+    let mut update_fields = Vec::new();
+    let mut search_columns = Vec::new();
+    let mut columns = Vec::new();
+    let mut archive_column = None;
+    let mut version_field = None;
+
+    let mut serialized_fields = Vec::new();
+
+    for field in fields {
+        if !skips_serializing(field)? {
+            serialized_fields.push(field.ident.as_ref().expect("named field").to_string());
+        }
+
+        let options = field_options(field)?;
+        if options.skip {
+            continue;
+        }
+
+        let field_ident = field.ident.as_ref().expect("named field");
+        let name = field_ident.to_string();
+
+        if options.create {
+            create_fields.push(field_ident);
+        }
+        if options.update {
+            update_fields.push(field_ident);
+        }
+        if options.search {
+            search_columns.push(name.clone());
+        }
+        if options.archive {
+            if archive_column.is_some() {
+                return Err(Error::new_spanned(field, "only one field can be `archive`"));
+            }
+            archive_column = Some(name.clone());
+        }
+        if options.version {
+            if version_field.is_some() {
+                return Err(Error::new_spanned(field, "only one field can be `version`"));
+            }
+            version_field = Some(field_ident);
+        }
+        if options.sortable || options.filterable {
+            columns.push(column_tokens(field, &name, &options)?);
+        }
+    }
+
+    let create_names = create_fields.iter().map(|field| field.to_string());
+    let update_names: Vec<String> = update_fields
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+    let (update_model, changeset_from) = match &update {
+        Some(update) => (
+            quote!(#update),
+            Some(quote! {
+                impl ::std::convert::From<#update> for crate::model::patch::Changeset {
+                    fn from(data: #update) -> Self {
+                        crate::model::patch::Changeset::new()
+                            #(.patch(#update_names, data.#update_fields))*
+                    }
+                }
+            }),
+        ),
+        None => (quote!(crate::model::patch::Changeset), None),
+    };
+    let etag = version_field.map(|field| {
+        quote! {
+            fn etag(&self) -> ::std::option::Option<crate::model::etag::ETag> {
+                crate::model::etag::ETag::from_version(&self.#field)
+            }
+        }
+    });
+    let search_vector = search_vector.map(|column| {
+        quote! {
+            const SEARCH_VECTOR: ::std::option::Option<&'static str> =
+                ::std::option::Option::Some(#column);
+        }
+    });
+    let audited = unaudited.then(|| {
+        quote!(
+            const AUDITED: bool = false;
+        )
+    });
+    let archive_column = archive_column.map(|column| {
+        quote! {
+            const ARCHIVE_COLUMN: ::std::option::Option<&'static str> =
+                ::std::option::Option::Some(#column);
+        }
+    });
+
+    Ok(quote! {
+        impl crate::model::repository::ModelRepository for #ident {
+            type CreateModel = #create;
+
+            type UpdateModel = #update_model;
+
+            const TABLE_NAME: &'static str = #table;
+
+            const CREATE_FIELDS: &'static [&'static str] = &[#(#create_names),*];
+
+            const UPDATE_FIELDS: &'static [&'static str] = &[#(#update_names),*];
+
+            const SEARCH_COLUMNS: &'static [&'static str] = &[#(#search_columns),*];
+
+            const FIELDS: &'static [&'static str] = &[#(#serialized_fields),*];
+
+            const COLUMNS: &'static [crate::model::query::Column] = &[#(#columns),*];
+
+            #archive_column
+
+            #audited
+
+            #search_vector
+
+            #etag
+
+            fn bind_create(
+                query: crate::model::repository::PgQuery<'_, Self>,
+                data: Self::CreateModel,
+            ) -> crate::model::repository::PgQuery<'_, Self> {
+                query #(.bind(data.#create_fields))*
+            }
+
+            fn push_create_values<'args>(
+                row: &mut ::sqlx::query_builder::Separated<'_, 'args, ::sqlx::Postgres, &'static str>,
+                data: Self::CreateModel,
+            ) {
+                #(row.push_bind(data.#create_fields);)*
+            }
+        }
+
+        #changeset_from
+    })
+}
+
+/// Whether serde leaves the field out of the model's serialized form.
+fn skips_serializing(field: &syn::Field) -> syn::Result<bool> {
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+    {
+        let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        if metas.iter().any(|meta| {
+            matches!(meta, Meta::Path(path)
+                if path.is_ident("skip") || path.is_ident("skip_serializing"))
+        }) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("model"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            if path.is_ident("create") {
+                options.create = true;
+            } else if path.is_ident("update") {
+                options.update = true;
+            } else if path.is_ident("search") {
+                options.search = true;
+            } else if path.is_ident("sortable") {
+                options.sortable = true;
+            } else if path.is_ident("filterable") {
+                options.filterable = true;
+            } else if path.is_ident("indexed") {
+                options.sortable = true;
+                options.filterable = true;
+            } else if path.is_ident("archive") {
+                options.archive = true;
+            } else if path.is_ident("version") {
+                options.version = true;
+            } else if path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error(
+                    "expected one of `create`, `update`, `search`, `sortable`, \
+                     `filterable`, `indexed`, `archive`, `version` or `skip`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Builds the `Column` constant for a sortable or filterable field.
+fn column_tokens(
+    field: &syn::Field,
+    name: &str,
+    options: &FieldOptions,
+) -> syn::Result<TokenStream2> {
+    let (inner, nullable) = match option_inner(&field.ty) {
+        Some(inner) => (inner, true),
+        None => (&field.ty, false),
+    };
+
+    let constructor = match last_segment(inner).as_deref() {
+        Some("String") => quote!(text),
+        Some("i32") => quote!(int),
+        Some("i64") => quote!(bigint),
+        Some("bool") => quote!(bool),
+        Some("DateTime") => quote!(timestamp),
+        _ => {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "cannot infer a column kind; expected String, i32, i64, bool or DateTime",
+            ))
+        }
+    };
+
+    let nullable = nullable.then(|| quote!(.nullable()));
+    let sortable = options.sortable.then(|| quote!(.sortable()));
+    let filterable = options.filterable.then(|| quote!(.filterable()));
+
+    Ok(quote! {
+        crate::model::query::Column::#constructor(#name) #nullable #sortable #filterable
+    })
+}
+
+fn last_segment(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand_error(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn requires_table_and_create() {
+        let input = parse_quote! {
+            #[model(create = ThingForCreate)]
+            struct Thing { id: i32 }
+        };
+        assert_eq!(
+            expand_error(input),
+            "missing `#[model(table = ...)]` attribute"
+        );
+
+        let input = parse_quote! {
+            #[model(table = "things")]
+            struct Thing { id: i32 }
+        };
+        assert_eq!(
+            expand_error(input),
+            "missing `#[model(create = ...)]` attribute"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_struct_options() {
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate, audited)]
+            struct Thing { id: i32 }
+        };
+        assert_eq!(
+            expand_error(input),
+            "expected `table`, `create`, `update`, `unaudited` or `search_vector`"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_field_options() {
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing {
+                #[model(create, unique)]
+                name: String,
+            }
+        };
+        assert!(expand_error(input).starts_with("expected one of `create`, `update`"));
+    }
+
+    #[test]
+    fn rejects_tuple_structs_and_enums() {
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing(i32);
+        };
+        assert_eq!(expand_error(input), "expected a struct with named fields");
+
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            enum Thing { One }
+        };
+        assert_eq!(expand_error(input), "expected a struct");
+    }
+
+    #[test]
+    fn allows_one_archive_and_one_version_field() {
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing {
+                #[model(archive)]
+                archived_at: Option<DateTime<Utc>>,
+                #[model(archive)]
+                deleted_at: Option<DateTime<Utc>>,
+            }
+        };
+        assert_eq!(expand_error(input), "only one field can be `archive`");
+
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing {
+                #[model(version)]
+                updated_at: Option<DateTime<Utc>>,
+                #[model(version)]
+                revision: i32,
+            }
+        };
+        assert_eq!(expand_error(input), "only one field can be `version`");
+    }
+
+    #[test]
+    fn infers_column_kinds_from_field_types() {
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing {
+                #[model(indexed)]
+                id: i64,
+                #[model(filterable)]
+                owner_id: Option<i32>,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        assert!(expanded.contains(r#"Column :: bigint ("id")"#));
+        assert!(expanded.contains(r#"Column :: int ("owner_id") . nullable ()"#));
+
+        let input = parse_quote! {
+            #[model(table = "things", create = ThingForCreate)]
+            struct Thing {
+                #[model(sortable)]
+                price: f64,
+            }
+        };
+        assert_eq!(
+            expand_error(input),
+            "cannot infer a column kind; expected String, i32, i64, bool or DateTime"
+        );
+    }
+}
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS update_updated_at ON user_has_roles;
+
+DROP TABLE IF EXISTS user_has_roles;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS user_has_roles (
+   user_id INTEGER NOT NULL,
+   role_id INTEGER NOT NULL,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   PRIMARY KEY (user_id, role_id),
+   CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+   CONSTRAINT fk_role FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE
+);
+
+CREATE TRIGGER update_updated_at
+BEFORE UPDATE on user_has_roles
+FOR EACH ROW
+EXECUTE FUNCTION set_updated_at();
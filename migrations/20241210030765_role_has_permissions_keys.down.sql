@@ -0,0 +1,4 @@
+ALTER TABLE role_has_permissions
+DROP CONSTRAINT IF EXISTS fk_role,
+DROP CONSTRAINT IF EXISTS fk_permission,
+DROP CONSTRAINT IF EXISTS pk_role_has_permissions;
@@ -0,0 +1,4 @@
+ALTER TABLE role_has_permissions
+ADD CONSTRAINT pk_role_has_permissions PRIMARY KEY (role_id, permission_id),
+ADD CONSTRAINT fk_permission FOREIGN KEY (permission_id) REFERENCES permissions (id) ON DELETE CASCADE,
+ADD CONSTRAINT fk_role FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+   id BIGSERIAL PRIMARY KEY,
+   -- No foreign key: history outlives the users who made it
+   actor_id INTEGER,
+   table_name VARCHAR NOT NULL,
+   row_id INTEGER NOT NULL,
+   action VARCHAR NOT NULL,
+   before JSONB,
+   after JSONB,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_record ON audit_log (table_name, row_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log (actor_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at);
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS update_updated_at ON roles;
+
+ALTER TABLE roles
+    DROP COLUMN IF EXISTS created_at,
+    DROP COLUMN IF EXISTS updated_at;
@@ -0,0 +1,13 @@
+ALTER TABLE users DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE customers DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE contacts DROP COLUMN IF EXISTS search_vector;
+
+DROP INDEX IF EXISTS idx_users_name_trgm;
+DROP INDEX IF EXISTS idx_users_email_trgm;
+DROP INDEX IF EXISTS idx_customers_name_trgm;
+DROP INDEX IF EXISTS idx_customers_suburb_trgm;
+DROP INDEX IF EXISTS idx_customers_postcode_trgm;
+DROP INDEX IF EXISTS idx_contacts_first_name_trgm;
+DROP INDEX IF EXISTS idx_contacts_last_name_trgm;
+DROP INDEX IF EXISTS idx_contacts_phone_trgm;
+DROP INDEX IF EXISTS idx_contacts_email_trgm;
@@ -0,0 +1,44 @@
+-- One row per login; refreshing rotates its token but keeps the session
+CREATE TABLE IF NOT EXISTS sessions (
+   id SERIAL PRIMARY KEY,
+   user_id INTEGER NOT NULL,
+   -- `jti` of the newest access token, revoked along with the session
+   access_jti VARCHAR NOT NULL,
+   expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+   revoked_at TIMESTAMP WITH TIME ZONE,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions (user_id);
+
+CREATE TRIGGER update_updated_at
+BEFORE UPDATE ON sessions
+FOR EACH ROW
+EXECUTE FUNCTION set_updated_at();
+
+-- Every refresh token a session was given, stored as a SHA-256 hash. Only the
+-- one that has not been rotated is usable; presenting an older one means it
+-- leaked, and the whole session is revoked.
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+   token_hash VARCHAR PRIMARY KEY,
+   session_id INTEGER NOT NULL,
+   rotated_at TIMESTAMP WITH TIME ZONE,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   CONSTRAINT fk_session FOREIGN KEY (session_id) REFERENCES sessions (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session ON refresh_tokens (session_id);
+
+-- Access tokens rejected before they expire; rows can go once they have
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+   jti VARCHAR PRIMARY KEY,
+   expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens (expires_at);
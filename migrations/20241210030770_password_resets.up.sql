@@ -0,0 +1,12 @@
+-- Password reset tokens, stored as SHA-256 hashes. Each can be used once.
+CREATE TABLE IF NOT EXISTS password_resets (
+   token_hash VARCHAR PRIMARY KEY,
+   user_id INTEGER NOT NULL,
+   expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+   used_at TIMESTAMP WITH TIME ZONE,
+
+   created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+   CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_resets_user ON password_resets (user_id);
@@ -0,0 +1,3 @@
+-- When the last verification link went out, for throttling resends
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS email_verification_sent_at TIMESTAMP WITH TIME ZONE;
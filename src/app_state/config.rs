@@ -1,4 +1,4 @@
-use std::env;
+use std::{env, time::Duration};
 
 use base64::{engine::general_purpose, Engine};
 
@@ -6,10 +6,47 @@ use crate::error::AppResult;
 
 pub fn get_app_key() -> AppResult<Vec<u8>> {
     let raw_key = env::var("APP_KEY").expect("APP_KEY must be set");
-    if raw_key.starts_with("base64:") {
-        let base64_key = &raw_key[7..];
+    if let Some(base64_key) = raw_key.strip_prefix("base64:") {
         Ok(general_purpose::STANDARD.decode(base64_key)?)
     } else {
         Ok(raw_key.into_bytes())
     }
 }
+
+/// The public base URL of the app, used in links sent to users; from
+/// `APP_URL`.
+pub fn get_app_url() -> String {
+    env::var("APP_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
+}
+
+/// How long issued tokens stay valid.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenLifetimes {
+    pub access: Duration,
+    pub refresh: Duration,
+    pub password_reset: Duration,
+    pub email_verification: Duration,
+}
+
+/// Reads `ACCESS_TOKEN_LIFETIME`, `REFRESH_TOKEN_LIFETIME`,
+/// `PASSWORD_RESET_TOKEN_LIFETIME` and `EMAIL_VERIFICATION_LIFETIME`, in
+/// seconds; access and password reset tokens default to an hour, email
+/// verification links to a day and refresh tokens to 30 days.
+pub fn get_token_lifetimes() -> TokenLifetimes {
+    TokenLifetimes {
+        access: lifetime("ACCESS_TOKEN_LIFETIME", 60 * 60),
+        refresh: lifetime("REFRESH_TOKEN_LIFETIME", 30 * 24 * 60 * 60),
+        password_reset: lifetime("PASSWORD_RESET_TOKEN_LIFETIME", 60 * 60),
+        email_verification: lifetime("EMAIL_VERIFICATION_LIFETIME", 24 * 60 * 60),
+    }
+}
+
+fn lifetime(var: &str, default: u64) -> Duration {
+    let seconds = match env::var(var) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number of seconds", var)),
+        Err(_) => default,
+    };
+    Duration::from_secs(seconds)
+}
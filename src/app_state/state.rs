@@ -3,10 +3,17 @@ use std::sync::Arc;
 use axum::extract::FromRef;
 use sqlx::PgPool;
 
+use crate::mail::Mailer;
+
+use super::config::TokenLifetimes;
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
     pub app_key: Vec<u8>,
+    pub app_url: String,
+    pub token_lifetimes: TokenLifetimes,
+    pub mailer: Arc<dyn Mailer>,
 }
 
 pub type SharedAppState = Arc<AppState>;
@@ -1,7 +1,33 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::error::AppResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    /// The token's own id, which logging out revokes.
+    pub jti: String,
+    /// The session the token was issued for.
+    pub sid: i32,
+}
+
+impl Claims {
+    /// Claims for an access token of `session_id`, valid for `lifetime`.
+    pub fn new(user_id: i32, session_id: i32, jti: &str, lifetime: Duration) -> AppResult<Self> {
+        let expiration = SystemTime::now().duration_since(UNIX_EPOCH)? + lifetime;
+        Ok(Claims {
+            sub: user_id.to_string(),
+            exp: expiration.as_secs() as usize,
+            jti: jti.to_string(),
+            sid: session_id,
+        })
+    }
+
+    /// The authenticated user's id, parsed from `sub`.
+    pub fn user_id(&self) -> Option<i32> {
+        self.sub.parse().ok()
+    }
 }
@@ -3,19 +3,11 @@ use crate::{
     error::{AppError, AppResult},
 };
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use std::time::{SystemTime, UNIX_EPOCH};
-
-pub fn generate_jwt(user_id: &str, app_key: &[u8]) -> AppResult<String> {
-    let expiration = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 3600; // Valid for 1 hour
-
-    let claims = Claims {
-        sub: user_id.to_owned(),
-        exp: expiration as usize,
-    };
 
+pub fn generate_jwt(claims: &Claims, app_key: &[u8]) -> AppResult<String> {
     Ok(encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(app_key),
     )?)
 }
@@ -1,3 +1,5 @@
 pub mod claims;
 pub mod jwt;
 pub mod security;
+pub mod tokens;
+pub mod verification;
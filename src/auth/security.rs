@@ -55,3 +55,51 @@ pub fn hash_password(
 
     Ok(password_hash)
 }
+
+/// Hashes `passwords` on the blocking thread pool. Argon2 is slow on
+/// purpose, so hashing on the async runtime would stall every other request
+/// on the same worker.
+pub async fn hash_passwords(
+    passwords: Vec<String>,
+    app_key: Vec<u8>,
+) -> Result<Vec<String>, argon2::password_hash::Error> {
+    tokio::task::spawn_blocking(move || {
+        passwords
+            .iter()
+            .map(|password| hash_password(password, &app_key))
+            .collect()
+    })
+    .await
+    .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// `hash_passwords` for a single password.
+pub async fn hash_password_in_background(
+    password: String,
+    app_key: Vec<u8>,
+) -> Result<String, argon2::password_hash::Error> {
+    let mut hashes = hash_passwords(vec![password], app_key).await?;
+    Ok(hashes.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_KEY: &[u8] = b"abcdefghijklmnopqrstuvwxyz123456";
+
+    #[tokio::test]
+    async fn hash_passwords_keeps_the_order() {
+        let passwords = vec!["first-secret".to_string(), "second-secret".to_string()];
+
+        let hashes = hash_passwords(passwords.clone(), APP_KEY.to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(hashes.len(), 2);
+        for (password, hash) in passwords.iter().zip(&hashes) {
+            assert!(verify_password(password, hash).unwrap());
+        }
+        assert!(!verify_password("first-secret", &hashes[1]).unwrap());
+    }
+}
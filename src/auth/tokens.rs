@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// A random, URL-safe token, used for refresh tokens and token ids.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The hex SHA-256 of `token`, which is what gets stored. The tokens are
+/// random, so they need no salt or slow hash.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// When a token issued now with `lifetime` expires.
+pub fn expires_at(lifetime: Duration) -> DateTime<Utc> {
+    TimeDelta::from_std(lifetime)
+        .ok()
+        .and_then(|lifetime| Utc::now().checked_add_signed(lifetime))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
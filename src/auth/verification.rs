@@ -0,0 +1,137 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error::AppResult;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The query of a signed email verification link. The signature covers the
+/// user's email address as well, so changing it voids earlier links.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationLink {
+    pub id: i32,
+    /// Unix time after which the link is refused.
+    pub expires: u64,
+    pub signature: String,
+}
+
+impl VerificationLink {
+    pub fn new(app_key: &[u8], user_id: i32, email: &str, lifetime: Duration) -> AppResult<Self> {
+        let expires = (SystemTime::now().duration_since(UNIX_EPOCH)? + lifetime).as_secs();
+        let signature = mac(app_key, user_id, email, expires)
+            .finalize()
+            .into_bytes();
+        Ok(VerificationLink {
+            id: user_id,
+            expires,
+            signature: general_purpose::URL_SAFE_NO_PAD.encode(signature),
+        })
+    }
+
+    /// The link to `/email/verify` on the app at `app_url`.
+    pub fn url(&self, app_url: &str) -> String {
+        format!(
+            "{}/email/verify?id={}&expires={}&signature={}",
+            app_url.trim_end_matches('/'),
+            self.id,
+            self.expires,
+            self.signature
+        )
+    }
+
+    /// Whether the link was signed for `email` and has not expired.
+    pub fn is_valid(&self, app_key: &[u8], email: &str) -> bool {
+        let expired = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(now) => now.as_secs() > self.expires,
+            Err(_) => true,
+        };
+        let Ok(signature) = general_purpose::URL_SAFE_NO_PAD.decode(&self.signature) else {
+            return false;
+        };
+        !expired
+            && mac(app_key, self.id, email, self.expires)
+                .verify_slice(&signature)
+                .is_ok()
+    }
+}
+
+fn mac(app_key: &[u8], user_id: i32, email: &str, expires: u64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(app_key).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}|{}|{}", user_id, email, expires).as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"test key";
+    const EMAIL: &str = "alice@example.com";
+
+    fn link() -> VerificationLink {
+        VerificationLink::new(KEY, 7, EMAIL, Duration::from_secs(3600)).unwrap()
+    }
+
+    #[test]
+    fn accepts_an_unexpired_link_for_the_same_email() {
+        assert!(link().is_valid(KEY, EMAIL));
+    }
+
+    #[test]
+    fn refuses_a_link_once_the_email_changes() {
+        assert!(!link().is_valid(KEY, "alice@example.org"));
+    }
+
+    #[test]
+    fn refuses_a_link_signed_with_another_key() {
+        assert!(!link().is_valid(b"other key", EMAIL));
+    }
+
+    #[test]
+    fn refuses_tampered_fields() {
+        let other_user = VerificationLink { id: 8, ..link() };
+        assert!(!other_user.is_valid(KEY, EMAIL));
+
+        let original = link();
+        let extended = VerificationLink {
+            expires: original.expires + 3600,
+            ..original
+        };
+        assert!(!extended.is_valid(KEY, EMAIL));
+
+        let garbled = VerificationLink {
+            signature: "not base64!".to_string(),
+            ..link()
+        };
+        assert!(!garbled.is_valid(KEY, EMAIL));
+    }
+
+    #[test]
+    fn refuses_an_expired_link() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let expires = now.as_secs() - 1;
+        let signature = mac(KEY, 7, EMAIL, expires).finalize().into_bytes();
+        let expired = VerificationLink {
+            id: 7,
+            expires,
+            signature: general_purpose::URL_SAFE_NO_PAD.encode(signature),
+        };
+        assert!(!expired.is_valid(KEY, EMAIL));
+    }
+
+    #[test]
+    fn url_carries_the_link_query() {
+        let link = link();
+        assert_eq!(
+            link.url("https://app.example.com/"),
+            format!(
+                "https://app.example.com/email/verify?id=7&expires={}&signature={}",
+                link.expires, link.signature
+            )
+        );
+    }
+}
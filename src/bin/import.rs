@@ -0,0 +1,123 @@
+use std::{env, fs, path::PathBuf, process};
+
+use asg::{
+    error::{AppError, AppResult},
+    routes::{
+        contacts::import::import_contacts,
+        customers::import::import_customers,
+        import::{ColumnMapping, ImportReport},
+        response::FieldErrors,
+    },
+};
+use clap::{Args, Parser, Subcommand};
+use sqlx::{Pool, Postgres};
+
+#[tokio::main]
+async fn main() -> AppResult<()> {
+    dotenv::dotenv().ok();
+
+    let cli = Cli::parse();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = Pool::<Postgres>::connect(&database_url).await?;
+
+    let (args, result) = match cli.command {
+        Commands::Customers(args) => {
+            let csv = read_file(&args);
+            let result = import_customers(&pool, &csv, &args.mapping(), args.dry_run).await;
+            (args, result)
+        }
+        Commands::Contacts(args) => {
+            let csv = read_file(&args);
+            let result = import_contacts(&pool, &csv, &args.mapping(), args.dry_run).await;
+            (args, result)
+        }
+    };
+
+    match result {
+        Ok(report) => {
+            print_report(&report);
+            if !report.errors.is_empty() {
+                process::exit(1);
+            }
+        }
+        Err(AppError::ValidationError(errors)) => {
+            eprintln!("Cannot import {}:", args.file.display());
+            print_errors(&errors);
+            process::exit(1);
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(())
+}
+
+#[derive(Parser)]
+#[command(name = "Importer")]
+#[command(about = "Import customers or contacts from a CSV file", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// One customer per row, optionally with a contact in `contact.*` fields
+    Customers(ImportArgs),
+    /// One contact per row, for the customer in `customer_id`
+    Contacts(ImportArgs),
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// The CSV file to import; the first line holds the column headers
+    file: PathBuf,
+
+    /// Read a column into a field, as `Column=field`; may be repeated
+    #[arg(long = "map", value_parser = parse_mapping)]
+    map: Vec<(String, String)>,
+
+    /// Check every row and report errors without saving anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl ImportArgs {
+    fn mapping(&self) -> ColumnMapping {
+        self.map.iter().cloned().collect()
+    }
+}
+
+fn parse_mapping(value: &str) -> Result<(String, String), String> {
+    match value.rsplit_once('=') {
+        Some((column, field)) if !column.is_empty() && !field.is_empty() => {
+            Ok((column.to_string(), field.to_string()))
+        }
+        _ => Err(format!("expected `Column=field`, got `{}`", value)),
+    }
+}
+
+fn read_file(args: &ImportArgs) -> Vec<u8> {
+    fs::read(&args.file).unwrap_or_else(|err| {
+        eprintln!("Cannot read {}: {}", args.file.display(), err);
+        process::exit(1);
+    })
+}
+
+fn print_report(report: &ImportReport) {
+    for error in &report.errors {
+        println!("Row {}:", error.row);
+        print_errors(&error.errors);
+    }
+    println!("{}", report.message());
+}
+
+fn print_errors(errors: &FieldErrors) {
+    let mut fields: Vec<_> = errors.iter().collect();
+    fields.sort();
+    for (field, messages) in fields {
+        for message in messages {
+            println!("  {}: {}", field, message);
+        }
+    }
+}
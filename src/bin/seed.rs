@@ -5,7 +5,9 @@ use asg::{
     auth::security::hash_password,
     error::AppResult,
     model::{
+        permission_catalogue::{self, ADMIN_ROLE},
         repository::ModelRepository,
+        roles::Role,
         users::{User, UserForCreate},
     },
 };
@@ -25,11 +27,15 @@ async fn main() -> AppResult<()> {
     match &cli.command {
         Some(Commands::All) => {
             seed_users(&pool, &app_key).await?;
+            seed_roles(&pool).await?;
             seed_customers(&pool, &app_key).await?;
         }
         Some(Commands::Users) => {
             seed_users(&pool, &app_key).await?;
         }
+        Some(Commands::Roles) => {
+            seed_roles(&pool).await?;
+        }
         Some(Commands::Customers) => {}
         None => println!("No seeding command provided. Use --help for options."),
     }
@@ -49,6 +55,7 @@ struct Cli {
 enum Commands {
     All,
     Users,
+    Roles,
     Customers,
 }
 
@@ -57,12 +64,12 @@ async fn seed_users(pool: &Pool<Postgres>, app_key: &[u8]) -> AppResult<()> {
         UserForCreate {
             name: "Alice".to_string(),
             email: "alice@example.com".to_string(),
-            hashed_password: hash_password("secret123", app_key)?,
+            password: hash_password("secret123", app_key)?,
         },
         UserForCreate {
             name: "Bob".to_string(),
             email: "bob@example.com".to_string(),
-            hashed_password: hash_password("secret456", app_key)?,
+            password: hash_password("secret456", app_key)?,
         },
     ];
 
@@ -83,6 +90,30 @@ async fn seed_users(pool: &Pool<Postgres>, app_key: &[u8]) -> AppResult<()> {
     Ok(())
 }
 
+async fn seed_roles(pool: &Pool<Postgres>) -> AppResult<()> {
+    let report = permission_catalogue::sync(pool).await?;
+    if !report.inserted.is_empty() {
+        println!("Permissions added: {}", report.inserted.join(", "));
+    }
+    if !report.created_roles.is_empty() {
+        println!("Default roles created: {}", report.created_roles.join(", "));
+    }
+    if !report.orphaned.is_empty() {
+        println!(
+            "Permissions not declared in the catalogue: {}",
+            report.orphaned.join(", ")
+        );
+    }
+
+    let admin = Role::find_by_name(pool, ADMIN_ROLE).await?;
+    match User::get_password_hash(pool, "alice@example.com").await {
+        Ok((user_id, _)) => Role::assign_to_user(pool, admin.id, user_id).await?,
+        Err(err) => eprintln!("Error assigning admin role: {}", err),
+    }
+
+    Ok(())
+}
+
 async fn seed_customers(_pool: &Pool<Postgres>, _app_key: &[u8]) -> AppResult<()> {
     Ok(())
 }
@@ -0,0 +1,31 @@
+use std::{future::Future, pin::Pin};
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::error::AppResult;
+
+pub type Tx = Transaction<'static, Postgres>;
+
+/// Runs `work` as one unit of work: the transaction is committed when it
+/// returns `Ok` and rolled back when it returns `Err`.
+///
+/// Repository methods accept `&mut **tx` in place of the pool.
+pub async fn transaction<T, F>(pool: &PgPool, work: F) -> AppResult<T>
+where
+    F: for<'t> FnOnce(&'t mut Tx) -> Pin<Box<dyn Future<Output = AppResult<T>> + Send + 't>>,
+{
+    let mut tx = pool.begin().await?;
+
+    match work(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(rollback_err) = tx.rollback().await {
+                tracing::error!("Failed to roll back transaction: {:?}", rollback_err);
+            }
+            Err(err)
+        }
+    }
+}
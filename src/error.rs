@@ -1,4 +1,5 @@
 use core::fmt;
+use std::collections::HashMap;
 
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -12,7 +13,16 @@ pub enum AppError {
     Base64DecodeError(base64::DecodeError),
     InvalidCredentials,
     Unauthorized,
+    /// The caller lacks a permission the request needs.
+    Forbidden,
     InvalidPasswordHash,
+    /// The row no longer matches the version the client sent in `If-Match`.
+    PreconditionFailed,
+    /// Writing a CSV or XLSX export failed.
+    ExportError(Box<dyn std::error::Error + Send + Sync>),
+    /// Building or sending an email failed.
+    MailError(Box<dyn std::error::Error + Send + Sync>),
+    ValidationError(HashMap<String, Vec<String>>),
 }
 
 impl fmt::Display for AppError {
@@ -27,6 +37,21 @@ impl fmt::Display for AppError {
             AppError::InvalidCredentials => write!(f, "Invalid credentials"),
             AppError::InvalidPasswordHash => write!(f, "Invalid password hash"),
             AppError::Unauthorized => write!(f, "Unauthorized"),
+            AppError::Forbidden => write!(f, "Forbidden"),
+            AppError::PreconditionFailed => write!(f, "Precondition failed"),
+            AppError::ExportError(err) => write!(f, "Export error: {}", err),
+            AppError::MailError(err) => write!(f, "Mail error: {}", err),
+            AppError::ValidationError(errors) => write!(f, "Validation error: {:?}", errors),
+        }
+    }
+}
+
+impl AppError {
+    /// Name of the database constraint that rejected the query, if any.
+    pub fn constraint(&self) -> Option<&str> {
+        match self {
+            AppError::DatabaseError(sqlx::Error::Database(err)) => err.constraint(),
+            _ => None,
         }
     }
 }
@@ -2,6 +2,7 @@ pub mod app_state;
 pub mod auth;
 pub mod db;
 pub mod error;
+pub mod mail;
 pub mod middleware;
 pub mod model;
 pub mod routes;
@@ -0,0 +1,50 @@
+use std::{env, path::PathBuf};
+
+use axum::async_trait;
+use chrono::Utc;
+use lettre::message::Mailbox;
+
+use crate::{auth::tokens::random_token, error::AppResult};
+
+use super::{mail_error, Mailer, Message};
+
+/// Writes each message to an `.eml` file in `MAIL_PATH` (`logs/mail` by
+/// default) instead of sending it, so it can be opened in a mail client.
+#[derive(Debug)]
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn from_env(from: Mailbox) -> Self {
+        let dir = env::var("MAIL_PATH").unwrap_or_else(|_| "logs/mail".to_string());
+        FileMailer {
+            from,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: Message) -> AppResult<()> {
+        let email = message.to_email(&self.from)?;
+        // Sorts by time; the random part keeps messages sent together apart
+        let name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%.3f"),
+            &random_token()[..8]
+        );
+        let path = self.dir.join(name);
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(mail_error)?;
+        tokio::fs::write(&path, email.formatted())
+            .await
+            .map_err(mail_error)?;
+        tracing::info!("Mail to {} written to {}", message.to, path.display());
+        Ok(())
+    }
+}
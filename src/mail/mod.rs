@@ -0,0 +1,96 @@
+//! Outbound email.
+//!
+//! Messages are rendered from the templates in [`templates`] and handed to
+//! a [`Mailer`], chosen by `MAIL_MAILER`:
+//!
+//! - `smtp` sends through `MAIL_HOST`, see [`smtp::SmtpMailer`];
+//! - `file` writes `.eml` files to `MAIL_PATH`, for development;
+//! - `log` (the default) only writes messages to the log.
+//!
+//! Every mailer sends from `MAIL_FROM_ADDRESS`, named `MAIL_FROM_NAME`.
+
+use std::{env, fmt::Debug, sync::Arc};
+
+use axum::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+
+use crate::error::{AppError, AppResult};
+
+pub mod file;
+pub mod log;
+pub mod smtp;
+pub mod templates;
+
+/// A rendered email with an HTML and a plain text body.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, message: Message) -> AppResult<()>;
+}
+
+impl Message {
+    /// Builds the MIME message, sent from `from`.
+    pub fn to_email(&self, from: &Mailbox) -> AppResult<lettre::Message> {
+        let to: Mailbox = self.to.parse().map_err(mail_error)?;
+        lettre::Message::builder()
+            .from(from.clone())
+            .to(to)
+            .subject(&self.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                self.text.clone(),
+                self.html.clone(),
+            ))
+            .map_err(mail_error)
+    }
+}
+
+/// Sends `message` without waiting for it; failures are logged. Used where
+/// the response must not depend on whether, or how quickly, mail goes out.
+pub fn send_in_background(mailer: Arc<dyn Mailer>, message: Message) {
+    tokio::spawn(async move {
+        let to = message.to.clone();
+        if let Err(err) = mailer.send(message).await {
+            tracing::error!("Failed to send mail to {}: {:?}", to, err);
+        }
+    });
+}
+
+/// The mailer configured by the environment.
+pub fn from_env() -> AppResult<Arc<dyn Mailer>> {
+    let from = from_mailbox()?;
+    let mailer = env::var("MAIL_MAILER").unwrap_or_else(|_| "log".to_string());
+    Ok(match mailer.as_str() {
+        "smtp" => Arc::new(smtp::SmtpMailer::from_env(from)?),
+        "file" => Arc::new(file::FileMailer::from_env(from)),
+        "log" => Arc::new(log::LogMailer::new(from)),
+        other => {
+            return Err(mail_error(format!(
+                "MAIL_MAILER must be smtp, file or log, not {}",
+                other
+            )))
+        }
+    })
+}
+
+fn from_mailbox() -> AppResult<Mailbox> {
+    let address = env::var("MAIL_FROM_ADDRESS").unwrap_or_else(|_| "noreply@localhost".to_string());
+    let name = env::var("MAIL_FROM_NAME").unwrap_or_else(|_| "ASG".to_string());
+    Ok(Mailbox::new(
+        Some(name),
+        address.parse().map_err(mail_error)?,
+    ))
+}
+
+pub(crate) fn mail_error<E>(err: E) -> AppError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    AppError::MailError(err.into())
+}
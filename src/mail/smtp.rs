@@ -0,0 +1,66 @@
+use std::env;
+
+use axum::async_trait;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::{authentication::Credentials, AsyncSmtpTransport},
+    AsyncTransport, Tokio1Executor,
+};
+
+use crate::error::AppResult;
+
+use super::{mail_error, Mailer, Message};
+
+/// Sends mail through an SMTP server.
+///
+/// Configured by `MAIL_HOST`, `MAIL_PORT`, `MAIL_USERNAME`, `MAIL_PASSWORD`
+/// and `MAIL_ENCRYPTION`, which is `tls`, `starttls` (the default) or `none`.
+/// A local catcher such as Mailpit needs `MAIL_ENCRYPTION=none` and its
+/// port, usually 1025.
+#[derive(Debug)]
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn from_env(from: Mailbox) -> AppResult<Self> {
+        let host = env::var("MAIL_HOST").unwrap_or_else(|_| "localhost".to_string());
+        let encryption = env::var("MAIL_ENCRYPTION").unwrap_or_else(|_| "starttls".to_string());
+        let mut builder = match encryption.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host).map_err(mail_error)?,
+            "starttls" => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host).map_err(mail_error)?
+            }
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            other => {
+                return Err(mail_error(format!(
+                    "MAIL_ENCRYPTION must be tls, starttls or none, not {}",
+                    other
+                )))
+            }
+        };
+
+        if let Ok(port) = env::var("MAIL_PORT") {
+            builder = builder.port(port.parse().map_err(mail_error)?);
+        }
+        if let (Ok(username), Ok(password)) = (env::var("MAIL_USERNAME"), env::var("MAIL_PASSWORD"))
+        {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: Message) -> AppResult<()> {
+        let email = message.to_email(&self.from)?;
+        self.transport.send(email).await.map_err(mail_error)?;
+        Ok(())
+    }
+}
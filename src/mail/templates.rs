@@ -0,0 +1,197 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::error::AppResult;
+
+use super::{mail_error, Message};
+
+/// The values a template's `{{ name }}` placeholders are replaced with.
+pub type Variables = HashMap<&'static str, String>;
+
+/// An email template. The subject, HTML and text bodies may all use
+/// `{{ name }}` placeholders; values are HTML-escaped in the HTML body.
+#[derive(Debug, Clone, Copy)]
+pub struct Template {
+    pub subject: &'static str,
+    pub html: &'static str,
+    pub text: &'static str,
+}
+
+/// Variables: `name`, `url` and `expires_in`.
+pub const VERIFY_EMAIL: Template = Template {
+    subject: "Verify your email address",
+    html: include_str!("../../templates/mail/verify_email.html"),
+    text: include_str!("../../templates/mail/verify_email.txt"),
+};
+
+/// Variables: `name`, `url`, `token` and `expires_in`.
+pub const PASSWORD_RESET: Template = Template {
+    subject: "Reset your password",
+    html: include_str!("../../templates/mail/password_reset.html"),
+    text: include_str!("../../templates/mail/password_reset.txt"),
+};
+
+impl Template {
+    /// The message to `to`. A placeholder without a value is an error, so
+    /// a message never goes out half-filled.
+    pub fn render(&self, to: &str, variables: &Variables) -> AppResult<Message> {
+        Ok(Message {
+            to: to.to_string(),
+            subject: render(self.subject, variables, false)?,
+            html: render(self.html, variables, true)?,
+            text: render(self.text, variables, false)?,
+        })
+    }
+}
+
+/// A lifetime as a template value, e.g. "1 hour" or "30 days".
+pub fn describe_lifetime(lifetime: Duration) -> String {
+    let seconds = lifetime.as_secs();
+    let (count, unit) = match seconds {
+        0 => (0, "second"),
+        s if s % 86400 == 0 => (s / 86400, "day"),
+        s if s % 3600 == 0 => (s / 3600, "hour"),
+        s if s % 60 == 0 => (s / 60, "minute"),
+        s => (s, "second"),
+    };
+    match count {
+        1 => format!("1 {}", unit),
+        count => format!("{} {}s", count, unit),
+    }
+}
+
+fn render(template: &str, variables: &Variables, escape: bool) -> AppResult<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let end = placeholder
+            .find("}}")
+            .ok_or_else(|| mail_error("Unclosed placeholder in mail template"))?;
+        let name = placeholder[..end].trim();
+        let value = variables
+            .get(name)
+            .ok_or_else(|| mail_error(format!("No value for mail template variable {}", name)))?;
+        if escape {
+            push_escaped(&mut rendered, value);
+        } else {
+            rendered.push_str(value);
+        }
+        rest = &placeholder[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn push_escaped(rendered: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => rendered.push_str("&amp;"),
+            '<' => rendered.push_str("&lt;"),
+            '>' => rendered.push_str("&gt;"),
+            '"' => rendered.push_str("&quot;"),
+            '\'' => rendered.push_str("&#39;"),
+            c => rendered.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREETING: Template = Template {
+        subject: "Hello {{ name }}",
+        html: "<p>Hi {{name}}, visit <a href=\"{{ url }}\">here</a>.</p>",
+        text: "Hi {{ name }}, visit {{ url }}.",
+    };
+
+    fn variables(pairs: &[(&'static str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn render_fills_every_part_and_escapes_only_html() {
+        let message = GREETING
+            .render(
+                "tom@example.com",
+                &variables(&[
+                    ("name", "Tom & <Jerry>"),
+                    ("url", "https://x.test/?a=1&b=\"2\""),
+                ]),
+            )
+            .unwrap();
+
+        assert_eq!(message.to, "tom@example.com");
+        assert_eq!(message.subject, "Hello Tom & <Jerry>");
+        assert_eq!(
+            message.html,
+            "<p>Hi Tom &amp; &lt;Jerry&gt;, visit \
+             <a href=\"https://x.test/?a=1&amp;b=&quot;2&quot;\">here</a>.</p>"
+        );
+        assert_eq!(
+            message.text,
+            "Hi Tom & <Jerry>, visit https://x.test/?a=1&b=\"2\"."
+        );
+    }
+
+    #[test]
+    fn render_fails_on_a_missing_variable() {
+        let err = GREETING
+            .render("tom@example.com", &variables(&[("name", "Tom")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Mail error: No value for mail template variable url"
+        );
+    }
+
+    #[test]
+    fn render_fails_on_an_unclosed_placeholder() {
+        let broken = Template {
+            subject: "Hello {{ name",
+            ..GREETING
+        };
+        let err = broken
+            .render(
+                "tom@example.com",
+                &variables(&[("name", "Tom"), ("url", "x")]),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Mail error: Unclosed placeholder in mail template"
+        );
+    }
+
+    #[test]
+    fn bundled_templates_render_with_their_documented_variables() {
+        let verify = variables(&[("name", "Tom"), ("url", "u"), ("expires_in", "1 hour")]);
+        assert!(VERIFY_EMAIL.render("tom@example.com", &verify).is_ok());
+
+        let reset = variables(&[
+            ("name", "Tom"),
+            ("url", "u"),
+            ("token", "t"),
+            ("expires_in", "1 hour"),
+        ]);
+        assert!(PASSWORD_RESET.render("tom@example.com", &reset).is_ok());
+    }
+
+    #[test]
+    fn describe_lifetime_uses_the_largest_whole_unit() {
+        let describe = |seconds| describe_lifetime(Duration::from_secs(seconds));
+        assert_eq!(describe(0), "0 seconds");
+        assert_eq!(describe(1), "1 second");
+        assert_eq!(describe(90), "90 seconds");
+        assert_eq!(describe(60), "1 minute");
+        assert_eq!(describe(5400), "90 minutes");
+        assert_eq!(describe(3600), "1 hour");
+        assert_eq!(describe(7 * 3600), "7 hours");
+        assert_eq!(describe(86400), "1 day");
+        assert_eq!(describe(30 * 86400), "30 days");
+    }
+}
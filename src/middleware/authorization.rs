@@ -6,7 +6,11 @@ use axum::{
     response::Response,
 };
 
-use crate::{app_state::SharedAppState, auth::jwt::decode_jwt};
+use crate::{
+    app_state::SharedAppState,
+    auth::jwt::decode_jwt,
+    model::{audit_log, sessions::Session},
+};
 
 pub async fn authorization(
     State(state): State<SharedAppState>,
@@ -21,23 +25,38 @@ pub async fn authorization(
                 // Validate the JWT token
                 match decode_jwt(token, &state.app_key) {
                     Ok(claims) => {
+                        // Logging out revokes tokens before they expire
+                        match Session::is_revoked(&state.db_pool, &claims.jti).await {
+                            Ok(false) => {}
+                            Ok(true) => return unauthorized("Invalid or expired token"),
+                            Err(err) => {
+                                tracing::error!("Failed to check token revocation: {:?}", err);
+                                return Response::builder()
+                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                                    .body("An unexpected error occurred".into())
+                                    .unwrap();
+                            }
+                        }
+
                         // Attach claims (e.g. email) to request extensions
+                        let actor_id = claims.user_id();
                         req.extensions_mut().insert(claims.sub.clone());
-                        return next.run(req).await;
-                    }
-                    Err(_) => {
-                        return Response::builder()
-                            .status(StatusCode::UNAUTHORIZED)
-                            .body("Invalid or expired token".into())
-                            .unwrap();
+                        req.extensions_mut().insert(claims);
+                        // Audited writes made by the handler are attributed to this user
+                        return audit_log::with_actor(actor_id, next.run(req)).await;
                     }
+                    Err(_) => return unauthorized("Invalid or expired token"),
                 }
             }
         }
     }
 
+    unauthorized("Missing or invalid Authorization header")
+}
+
+fn unauthorized(message: &'static str) -> Response {
     Response::builder()
         .status(StatusCode::UNAUTHORIZED)
-        .body("Missing or invalid Authorization header".into())
+        .body(message.into())
         .unwrap()
 }
@@ -1,6 +1,6 @@
 use axum::{
     body::{self, Body, Bytes},
-    http::Request,
+    http::{header, Request},
     middleware::Next,
     response::Response,
 };
@@ -8,8 +8,7 @@ use std::time::Instant;
 use tracing::{debug, info};
 
 pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
-    const MAX_BODY_SIZE: usize = 1 * 1024 * 1024; // 1MB
-    const SENSITIVE_KEYS: &[&str] = &["password", "api_key", "token"];
+    const MAX_BODY_SIZE: usize = 1024 * 1024; // 1MB
     const TRUNCATE_LIMIT: usize = 500;
     const USER_AGENT_HEADER: &str = "User-Agent";
     const UNKNOWN_USER_AGENT: &str = "[Unknown]";
@@ -21,13 +20,24 @@ pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
         .headers
         .get(USER_AGENT_HEADER)
         .map(|ua| ua.to_str().unwrap_or(UNKNOWN_USER_AGENT));
-    let body_bytes = body::to_bytes(body, MAX_BODY_SIZE)
-        .await
-        .unwrap_or(Bytes::new());
-    let req_body = redact_sensitive_info(&String::from_utf8_lossy(&body_bytes), SENSITIVE_KEYS);
 
-    // Reconstruct the request
-    let req = Request::from_parts(parts, Body::from(body_bytes.clone()));
+    // Only JSON bodies are logged; uploads such as CSV imports may be large
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let (req, req_body) = if is_json {
+        let body_bytes = body::to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .unwrap_or(Bytes::new());
+        let req_body = redact_sensitive_info(&String::from_utf8_lossy(&body_bytes));
+
+        // Reconstruct the request
+        (Request::from_parts(parts, Body::from(body_bytes)), req_body)
+    } else {
+        (Request::from_parts(parts, body), String::new())
+    };
 
     let method = req.method().clone();
     let uri = req.uri().clone();
@@ -37,13 +47,25 @@ pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
     let response = next.run(req).await;
     let duration = start.elapsed();
 
+    // File downloads are streamed and may be large; pass them through unread
+    if response.headers().contains_key(header::CONTENT_DISPOSITION) {
+        info!(
+            "Request {} {} | User-Agent: {:?} | Status: {} | Time: {:.2?} | Streamed download",
+            method,
+            uri,
+            user_agent.unwrap_or("[Unknown]"),
+            response.status(),
+            duration
+        );
+        return response;
+    }
+
     // Take ownership of the response body
     let (parts, body) = response.into_parts();
     let resp_body_bytes = body::to_bytes(body, MAX_BODY_SIZE)
         .await
         .unwrap_or_else(|_| Bytes::new());
-    let resp_body =
-        redact_sensitive_info(&String::from_utf8_lossy(&resp_body_bytes), SENSITIVE_KEYS);
+    let resp_body = redact_sensitive_info(&String::from_utf8_lossy(&resp_body_bytes));
 
     debug!(
         "Request: {} {} | User-Agent: {:?} | Body: {}\nResponse: {} | Body: {}\nTime: {:.2?}",
@@ -68,11 +90,26 @@ pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
     Response::from_parts(parts, Body::from(resp_body_bytes))
 }
 
-fn redact_sensitive_info(payload: &str, sensitive_keys: &[&str]) -> String {
+/// Keys whose values are never logged, besides any key ending in one of
+/// [`SENSITIVE_SUFFIXES`].
+const SENSITIVE_KEYS: &[&str] = &["api_key"];
+
+/// Catches `token`, `refresh_token`, `password`, `client_secret` and the like.
+const SENSITIVE_SUFFIXES: &[&str] = &["token", "secret", "password"];
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.contains(&key.as_str())
+        || SENSITIVE_SUFFIXES
+            .iter()
+            .any(|suffix| key.ends_with(suffix))
+}
+
+fn redact_sensitive_info(payload: &str) -> String {
     // Parse the payload as JSON
     match serde_json::from_str::<serde_json::Value>(payload) {
         Ok(mut json) => {
-            redact_json(&mut json, sensitive_keys); // Redact keys in-place
+            redact_json(&mut json); // Redact keys in-place
             json.to_string() // return redacted JSON as a string
         }
         Err(_) => payload.to_string(), // If parsing fails, return the original payload
@@ -80,20 +117,20 @@ fn redact_sensitive_info(payload: &str, sensitive_keys: &[&str]) -> String {
 }
 
 /// Recursively redact sensitive keys in JSON
-fn redact_json(value: &mut serde_json::Value, sensitive_keys: &[&str]) {
+fn redact_json(value: &mut serde_json::Value) {
     match value {
         serde_json::Value::Object(map) => {
             for (key, val) in map.iter_mut() {
-                if sensitive_keys.contains(&key.as_str()) {
+                if is_sensitive(key) {
                     *val = serde_json::Value::String("[REDACTED]".to_string());
                 } else {
-                    redact_json(val, sensitive_keys); // Recurse for nested objects
+                    redact_json(val); // Recurse for nested objects
                 }
             }
         }
         serde_json::Value::Array(array) => {
             for item in array {
-                redact_json(item, sensitive_keys); // Recurse for array elements
+                redact_json(item); // Recurse for array elements
             }
         }
         _ => {}
@@ -107,3 +144,59 @@ fn truncate(payload: &str, limit: usize) -> String {
         payload.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn redacted(payload: Value) -> Value {
+        serde_json::from_str(&redact_sensitive_info(&payload.to_string())).unwrap()
+    }
+
+    #[test]
+    fn redacts_a_refresh_request_body() {
+        assert_eq!(
+            redacted(json!({ "refresh_token": "r3fr35h" })),
+            json!({ "refresh_token": "[REDACTED]" })
+        );
+    }
+
+    #[test]
+    fn redacts_both_tokens_of_a_login_response() {
+        assert_eq!(
+            redacted(json!({ "token": "jwt", "refresh_token": "r3fr35h", "expires_in": 900 })),
+            json!({ "token": "[REDACTED]", "refresh_token": "[REDACTED]", "expires_in": 900 })
+        );
+    }
+
+    #[test]
+    fn redacts_nested_secrets_and_passwords() {
+        assert_eq!(
+            redacted(json!({
+                "users": [{ "email": "a@example.com", "password": "x", "new_Password": "y" }],
+                "client_secret": "s",
+                "api_key": "k",
+            })),
+            json!({
+                "users": [{
+                    "email": "a@example.com",
+                    "password": "[REDACTED]",
+                    "new_Password": "[REDACTED]",
+                }],
+                "client_secret": "[REDACTED]",
+                "api_key": "[REDACTED]",
+            })
+        );
+    }
+
+    #[test]
+    fn leaves_other_payloads_alone() {
+        assert_eq!(
+            redacted(json!({ "tokens_issued": 3, "name": "Acme" })),
+            json!({ "tokens_issued": 3, "name": "Acme" })
+        );
+        assert_eq!(redact_sensitive_info("not json"), "not json");
+    }
+}
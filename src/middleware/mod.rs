@@ -1,4 +1,8 @@
 mod authorization;
 mod log_requests;
+mod permissions;
+mod verified;
 pub use authorization::authorization;
 pub use log_requests::log_requests;
+pub use permissions::{require_permission, EffectivePermissions, PermissionGuard};
+pub use verified::require_verified_email;
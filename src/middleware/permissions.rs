@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    app_state::SharedAppState,
+    auth::claims::Claims,
+    model::{permission_catalogue::PermissionName, permissions::Permission},
+    routes::response,
+};
+
+/// State for [`require_permission`]: the permission a route demands.
+#[derive(Clone)]
+pub struct PermissionGuard {
+    pub state: SharedAppState,
+    pub permission: PermissionName,
+}
+
+impl PermissionGuard {
+    pub fn new(state: SharedAppState, permission: PermissionName) -> Self {
+        PermissionGuard { state, permission }
+    }
+}
+
+/// Permissions held by the caller, attached to the request once loaded.
+#[derive(Debug, Clone, Default)]
+pub struct EffectivePermissions(pub HashSet<String>);
+
+impl EffectivePermissions {
+    pub fn contains(&self, permission: PermissionName) -> bool {
+        self.0.contains(permission.as_str())
+    }
+}
+
+/// Rejects the request with 403 unless the caller holds the guarded permission.
+///
+/// Must run inside [`super::authorization`], which provides the caller's claims.
+pub async fn require_permission(
+    State(guard): State<PermissionGuard>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(user_id) = req.extensions().get::<Claims>().and_then(Claims::user_id) else {
+        return response::general_error::<()>(StatusCode::UNAUTHORIZED, "Unauthorized.")
+            .into_response();
+    };
+
+    let permissions = match req.extensions().get::<EffectivePermissions>() {
+        Some(permissions) => permissions.clone(),
+        None => match Permission::names_for_user(&guard.state.db_pool, user_id).await {
+            Ok(names) => EffectivePermissions(names.into_iter().collect()),
+            Err(err) => {
+                tracing::error!("Failed to load permissions: {:?}", err);
+                return response::unexpected_error::<()>().into_response();
+            }
+        },
+    };
+
+    if !permissions.contains(guard.permission) {
+        return response::general_error::<()>(
+            StatusCode::FORBIDDEN,
+            "You do not have permission to perform this action.",
+        )
+        .into_response();
+    }
+
+    req.extensions_mut().insert(permissions);
+    next.run(req).await
+}
@@ -0,0 +1,42 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    app_state::SharedAppState, auth::claims::Claims, model::users::User, routes::response,
+};
+
+/// Rejects the request with 403 until the caller has verified their email
+/// address.
+///
+/// Must run inside [`super::authorization`], which provides the caller's claims.
+pub async fn require_verified_email(
+    State(state): State<SharedAppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(user_id) = req.extensions().get::<Claims>().and_then(Claims::user_id) else {
+        return response::general_error::<()>(StatusCode::UNAUTHORIZED, "Unauthorized.")
+            .into_response();
+    };
+
+    match User::is_email_verified(&state.db_pool, user_id).await {
+        Ok(Some(true)) => next.run(req).await,
+        Ok(Some(false)) => response::general_error::<()>(
+            StatusCode::FORBIDDEN,
+            "Your email address is not verified.",
+        )
+        .into_response(),
+        Ok(None) => {
+            response::general_error::<()>(StatusCode::UNAUTHORIZED, "Unauthorized.").into_response()
+        }
+        Err(err) => {
+            tracing::error!("Failed to check email verification: {:?}", err);
+            response::unexpected_error::<()>().into_response()
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::future::Future;
+
+use asg_macros::ModelRepository;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::{prelude::FromRow, PgConnection};
+
+use crate::error::AppResult;
+
+use super::repository::ModelRepository;
+
+tokio::task_local! {
+    /// The user making the current request, set by the `authorization` middleware.
+    static ACTOR_ID: Option<i32>;
+}
+
+/// Runs `work` with `actor_id` recorded as the actor of every audited write.
+pub async fn with_actor<F: Future>(actor_id: Option<i32>, work: F) -> F::Output {
+    ACTOR_ID.scope(actor_id, work).await
+}
+
+/// The actor of the current request; `None` outside a request, e.g. in the
+/// seeder.
+pub fn current_actor() -> Option<i32> {
+    ACTOR_ID.try_with(|actor_id| *actor_id).ok().flatten()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Upsert,
+    Delete,
+    Archive,
+    Restore,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Upsert => "upsert",
+            AuditAction::Delete => "delete",
+            AuditAction::Archive => "archive",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(table = "audit_log", create = AuditEntryForCreate, unaudited)]
+pub struct AuditEntry {
+    #[model(indexed)]
+    pub id: i64,
+    #[model(create, filterable)]
+    pub actor_id: Option<i32>,
+    #[model(create, filterable)]
+    pub table_name: String,
+    #[model(create, filterable)]
+    pub row_id: i32,
+    #[model(create, filterable)]
+    pub action: String,
+    #[model(create)]
+    pub before: Option<Value>,
+    #[model(create)]
+    pub after: Option<Value>,
+    #[model(indexed)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+pub struct AuditEntryForCreate {
+    pub actor_id: Option<i32>,
+    pub table_name: String,
+    pub row_id: i32,
+    pub action: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Records a write to `M`'s table by the current actor.
+///
+/// `before` and `after` are the row as serialized for the API, so fields the
+/// API never shows (such as password hashes) are never logged. When both are
+/// given, only the fields that changed are kept; an update that changed
+/// nothing is not recorded.
+pub async fn record<M: ModelRepository>(
+    conn: &mut PgConnection,
+    action: AuditAction,
+    before: Option<&M>,
+    after: Option<&M>,
+) -> AppResult<()> {
+    if !M::AUDITED {
+        return Ok(());
+    }
+
+    let entries = entry::<M>(action, before, after)?.into_iter().collect();
+    AuditEntry::create_many(conn, entries).await?;
+    Ok(())
+}
+
+/// Like [`record`], for several writes at once.
+pub async fn record_many<M: ModelRepository>(
+    conn: &mut PgConnection,
+    writes: &[(AuditAction, Option<M>, Option<M>)],
+) -> AppResult<()> {
+    if !M::AUDITED {
+        return Ok(());
+    }
+
+    let mut entries = Vec::with_capacity(writes.len());
+    for (action, before, after) in writes {
+        entries.extend(entry::<M>(*action, before.as_ref(), after.as_ref())?);
+    }
+    AuditEntry::create_many(conn, entries).await?;
+    Ok(())
+}
+
+/// Records a row added to ([`AuditAction::Create`]) or removed from
+/// ([`AuditAction::Delete`]) the pivot `table`. Pivot rows have no id of
+/// their own, so the entry is filed under `owner_id`, the row on the "many"
+/// side the link belongs to, with `link` logged as the row.
+pub async fn record_link(
+    conn: &mut PgConnection,
+    table: &'static str,
+    action: AuditAction,
+    owner_id: i32,
+    link: Value,
+) -> AppResult<()> {
+    let (before, after) = match action {
+        AuditAction::Delete => (Some(link), None),
+        _ => (None, Some(link)),
+    };
+    AuditEntry::create(
+        conn,
+        AuditEntryForCreate {
+            actor_id: current_actor(),
+            table_name: table.to_string(),
+            row_id: owner_id,
+            action: action.as_str().to_string(),
+            before,
+            after,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// The id of a model row, as the API shows it.
+pub fn row_id<M: Serialize>(row: &M) -> Option<i32> {
+    let row = serde_json::to_value(row).ok()?;
+    row.get("id")?.as_i64()?.try_into().ok()
+}
+
+fn entry<M: ModelRepository>(
+    action: AuditAction,
+    before: Option<&M>,
+    after: Option<&M>,
+) -> AppResult<Option<AuditEntryForCreate>> {
+    let mut before = before.map(to_json).transpose()?;
+    let mut after = after.map(to_json).transpose()?;
+    let row_id = after
+        .as_ref()
+        .or(before.as_ref())
+        .and_then(|row| row.get("id")?.as_i64()?.try_into().ok());
+    let Some(row_id) = row_id else {
+        return Err(sqlx::Error::Protocol(format!(
+            "cannot audit a {} row without an id",
+            M::TABLE_NAME
+        ))
+        .into());
+    };
+
+    if let (Some(Value::Object(old)), Some(Value::Object(new))) = (&mut before, &mut after) {
+        diff(old, new);
+        if old.is_empty() && new.is_empty() {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(AuditEntryForCreate {
+        actor_id: current_actor(),
+        table_name: M::TABLE_NAME.to_string(),
+        row_id,
+        action: action.as_str().to_string(),
+        before,
+        after,
+    }))
+}
+
+fn to_json<M: Serialize>(row: &M) -> AppResult<Value> {
+    serde_json::to_value(row).map_err(|err| sqlx::Error::Encode(Box::new(err)).into())
+}
+
+/// Columns every update bumps, so they never count as a change of their own.
+const BUMPED_ON_UPDATE: [&str; 1] = ["updated_at"];
+
+/// Drops the keys whose value is the same on both sides, along with the
+/// `BUMPED_ON_UPDATE` ones.
+fn diff(before: &mut Map<String, Value>, after: &mut Map<String, Value>) {
+    let unchanged: Vec<String> = before
+        .iter()
+        .filter(|(key, value)| {
+            BUMPED_ON_UPDATE.contains(&key.as_str()) || after.get(*key) == Some(*value)
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in unchanged {
+        before.remove(&key);
+        after.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn diff_keeps_only_changed_fields() {
+        let mut before = object(json!({"id": 1, "name": "Acme", "updated_at": "2026-01-01"}));
+        let mut after = object(json!({"id": 1, "name": "Acme Ltd", "updated_at": "2026-01-02"}));
+
+        diff(&mut before, &mut after);
+
+        assert_eq!(Value::Object(before), json!({"name": "Acme"}));
+        assert_eq!(Value::Object(after), json!({"name": "Acme Ltd"}));
+    }
+
+    #[test]
+    fn diff_of_a_no_op_update_is_empty() {
+        let mut before = object(json!({"id": 1, "name": "Acme", "updated_at": "2026-01-01"}));
+        let mut after = object(json!({"id": 1, "name": "Acme", "updated_at": "2026-01-02"}));
+
+        diff(&mut before, &mut after);
+
+        assert!(before.is_empty() && after.is_empty());
+    }
+}
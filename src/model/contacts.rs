@@ -0,0 +1,77 @@
+use asg_macros::ModelRepository;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgExecutor};
+
+use crate::error::AppResult;
+
+use super::patch::Patch;
+
+/// Name of the CHECK constraint requiring at least one identifying field.
+pub const AT_LEAST_ONE_FIELD_CONSTRAINT: &str = "check_at_least_one_not_null";
+
+/// Fields covered by [`AT_LEAST_ONE_FIELD_CONSTRAINT`].
+pub const IDENTIFYING_FIELDS: &[&str] = &["first_name", "last_name", "phone", "email"];
+
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(
+    table = "contacts",
+    create = ContactForCreate,
+    update = ContactForUpdate,
+    search_vector = "search_vector",
+)]
+pub struct Contact {
+    #[model(indexed)]
+    pub id: i32,
+    #[model(create, update, search, indexed)]
+    pub first_name: Option<String>,
+    #[model(create, update, search, indexed)]
+    pub last_name: Option<String>,
+    #[model(create, update, indexed)]
+    pub position: Option<String>,
+    #[model(create, update, search, filterable)]
+    pub phone: Option<String>,
+    #[model(create, update, search, indexed)]
+    pub email: Option<String>,
+    #[model(create, indexed)]
+    pub customer_id: Option<i32>,
+    #[model(indexed)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[model(indexed, version)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub struct ContactForCreate {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub position: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub customer_id: i32,
+}
+
+#[derive(Default)]
+pub struct ContactForUpdate {
+    pub first_name: Patch<String>,
+    pub last_name: Patch<String>,
+    pub position: Patch<String>,
+    pub phone: Patch<String>,
+    pub email: Patch<String>,
+}
+
+impl Contact {
+    pub async fn belongs_to_customer<'e, E: PgExecutor<'e>>(
+        executor: E,
+        contact_id: i32,
+        customer_id: i32,
+    ) -> AppResult<bool> {
+        let exists: Option<i32> =
+            sqlx::query_scalar("SELECT 1 FROM contacts WHERE id = $1 AND customer_id = $2")
+                .bind(contact_id)
+                .bind(customer_id)
+                .fetch_optional(executor)
+                .await?;
+        Ok(exists.is_some())
+    }
+}
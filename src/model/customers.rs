@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgExecutor};
+
+use asg_macros::ModelRepository;
+use axum::async_trait;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::error::AppResult;
+
+use super::contacts::Contact;
+use super::include::{self, Related, Relation};
+use super::patch::Patch;
+use super::permission_catalogue::PermissionName;
+
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(
+    table = "customers",
+    create = CustomerForCreate,
+    update = CustomerForUpdate,
+    search_vector = "search_vector",
+)]
+pub struct Customer {
+    #[model(indexed)]
+    pub id: i32,
+    #[model(create, update, search, indexed)]
+    pub name: String,
+    #[model(create, update, filterable)]
+    pub address: Option<String>,
+    #[model(create, update)]
+    pub address_2: Option<String>,
+    #[model(create, update, search, indexed)]
+    pub suburb: Option<String>,
+    #[model(create, update, indexed)]
+    pub state: Option<String>,
+    #[model(create, update, search, indexed)]
+    pub postcode: Option<String>,
+    #[model(update, filterable)]
+    pub preferred_contact_id: Option<i32>,
+    #[model(create, update, indexed)]
+    pub terms: i32,
+    #[model(create, update, indexed)]
+    pub credit_limit: Option<i32>,
+    #[model(create, update, indexed)]
+    pub active: bool,
+    #[model(archive, indexed)]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[model(indexed)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[model(indexed, version)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub struct CustomerForCreate {
+    pub name: String,
+    pub address: Option<String>,
+    pub address_2: Option<String>,
+    pub suburb: Option<String>,
+    pub state: Option<String>,
+    pub postcode: Option<String>,
+    pub terms: i32,
+    pub credit_limit: Option<i32>,
+    pub active: bool,
+}
+
+#[derive(Default)]
+pub struct CustomerForUpdate {
+    pub name: Patch<String>,
+    pub address: Patch<String>,
+    pub address_2: Patch<String>,
+    pub suburb: Patch<String>,
+    pub state: Patch<String>,
+    pub postcode: Patch<String>,
+    pub terms: Patch<i32>,
+    pub credit_limit: Patch<i32>,
+    pub active: Patch<bool>,
+    pub preferred_contact_id: Patch<i32>,
+}
+
+impl Customer {
+    /// The subset of `ids` that belong to a customer.
+    pub async fn existing_ids<'e, E: PgExecutor<'e>>(
+        executor: E,
+        ids: &[i32],
+    ) -> AppResult<HashSet<i32>> {
+        let existing: Vec<i32> = sqlx::query_scalar("SELECT id FROM customers WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(executor)
+            .await?;
+        Ok(existing.into_iter().collect())
+    }
+}
+
+#[async_trait]
+impl Related for Customer {
+    const RELATIONS: &'static [Relation] = &[
+        Relation {
+            name: "contacts",
+            permission: PermissionName::ContactsView,
+        },
+        Relation {
+            name: "preferred_contact",
+            permission: PermissionName::ContactsView,
+        },
+    ];
+
+    async fn load_relation(pool: &PgPool, rows: &[Self], relation: &str) -> AppResult<Vec<Value>> {
+        let ids = || rows.iter().map(|customer| customer.id).collect();
+        match relation {
+            "contacts" => {
+                include::has_many::<Contact>(
+                    pool,
+                    "SELECT *, customer_id AS owner_id FROM contacts \
+                     WHERE customer_id = ANY($1) \
+                     ORDER BY id",
+                    ids(),
+                )
+                .await
+            }
+            "preferred_contact" => {
+                include::has_one::<Contact>(
+                    pool,
+                    "SELECT c.*, cu.id AS owner_id FROM contacts c \
+                     JOIN customers cu ON cu.preferred_contact_id = c.id \
+                     WHERE cu.id = ANY($1)",
+                    ids(),
+                )
+                .await
+            }
+            _ => Err(include::unknown_relation(relation)),
+        }
+    }
+}
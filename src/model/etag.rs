@@ -0,0 +1,192 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// A strong entity tag, derived from a row's version column.
+///
+/// Formats as the quoted header value, e.g. `"5f3a1c2b9d4e0"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+    pub fn from_version<V: Version>(version: &V) -> Option<Self> {
+        version.tag().map(ETag)
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.0)
+    }
+}
+
+/// Column types a row's version can be read from.
+pub trait Version {
+    fn tag(&self) -> Option<String>;
+}
+
+impl Version for DateTime<Utc> {
+    fn tag(&self) -> Option<String> {
+        Some(format!("{:x}", self.timestamp_micros()))
+    }
+}
+
+impl Version for i32 {
+    fn tag(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl Version for i64 {
+    fn tag(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl<T: Version> Version for Option<T> {
+    fn tag(&self) -> Option<String> {
+        self.as_ref().and_then(T::tag)
+    }
+}
+
+/// The value of an `If-Match` or `If-None-Match` header: `*`, or a list of
+/// entity tags. Malformed entries are dropped, so they never match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ETagCondition {
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityTag {
+    weak: bool,
+    opaque: String,
+}
+
+impl ETagCondition {
+    pub fn parse(header: &str) -> Self {
+        let header = header.trim();
+        if header == "*" {
+            return ETagCondition::Any;
+        }
+
+        let mut tags = Vec::new();
+        let mut rest = header;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+
+            let (weak, tail) = match rest.strip_prefix("W/") {
+                Some(tail) => (true, tail),
+                None => (false, rest),
+            };
+            let Some(tail) = tail.strip_prefix('"') else {
+                // Skip to the next entry
+                rest = rest.find(',').map_or("", |end| &rest[end..]);
+                continue;
+            };
+            let Some(end) = tail.find('"') else {
+                break;
+            };
+            tags.push(EntityTag {
+                weak,
+                opaque: tail[..end].to_string(),
+            });
+            rest = &tail[end + 1..];
+        }
+        ETagCondition::Tags(tags)
+    }
+
+    /// Strong comparison, as `If-Match` requires: weak tags never match.
+    /// `*` matches any existing row, versioned or not.
+    pub fn matches_strong(&self, etag: Option<&ETag>) -> bool {
+        match self {
+            ETagCondition::Any => true,
+            ETagCondition::Tags(tags) => {
+                etag.is_some_and(|etag| tags.iter().any(|tag| !tag.weak && tag.opaque == etag.0))
+            }
+        }
+    }
+
+    /// Weak comparison, as `If-None-Match` uses.
+    pub fn matches_weak(&self, etag: Option<&ETag>) -> bool {
+        match self {
+            ETagCondition::Any => true,
+            ETagCondition::Tags(tags) => {
+                etag.is_some_and(|etag| tags.iter().any(|tag| tag.opaque == etag.0))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(weak: bool, opaque: &str) -> EntityTag {
+        EntityTag {
+            weak,
+            opaque: opaque.to_string(),
+        }
+    }
+
+    fn etag(opaque: &str) -> ETag {
+        ETag(opaque.to_string())
+    }
+
+    #[test]
+    fn parses_a_wildcard() {
+        assert_eq!(ETagCondition::parse(" * "), ETagCondition::Any);
+    }
+
+    #[test]
+    fn parses_weak_and_strong_tags() {
+        assert_eq!(
+            ETagCondition::parse(r#""a1", W/"b2" ,"c,3""#),
+            ETagCondition::Tags(vec![tag(false, "a1"), tag(true, "b2"), tag(false, "c,3")])
+        );
+    }
+
+    #[test]
+    fn drops_malformed_entries() {
+        assert_eq!(
+            ETagCondition::parse(r#"a1, "b2", W/c3, "d4"#),
+            ETagCondition::Tags(vec![tag(false, "b2")])
+        );
+        assert_eq!(ETagCondition::parse(""), ETagCondition::Tags(vec![]));
+    }
+
+    #[test]
+    fn strong_comparison_ignores_weak_tags() {
+        let condition = ETagCondition::parse(r#"W/"a1", "b2""#);
+        assert!(!condition.matches_strong(Some(&etag("a1"))));
+        assert!(condition.matches_strong(Some(&etag("b2"))));
+        assert!(!condition.matches_strong(None));
+    }
+
+    #[test]
+    fn weak_comparison_accepts_either() {
+        let condition = ETagCondition::parse(r#"W/"a1", "b2""#);
+        assert!(condition.matches_weak(Some(&etag("a1"))));
+        assert!(condition.matches_weak(Some(&etag("b2"))));
+        assert!(!condition.matches_weak(Some(&etag("c3"))));
+    }
+
+    #[test]
+    fn wildcard_matches_unversioned_rows() {
+        assert!(ETagCondition::Any.matches_strong(None));
+        assert!(ETagCondition::Any.matches_weak(None));
+    }
+
+    #[test]
+    fn formats_as_a_quoted_header_value() {
+        let version = DateTime::from_timestamp_micros(0x5f3a1c2b9d4e0).unwrap();
+        assert_eq!(
+            ETag::from_version(&version).unwrap().to_string(),
+            r#""5f3a1c2b9d4e0""#
+        );
+        assert_eq!(ETag::from_version(&None::<i32>), None);
+    }
+}
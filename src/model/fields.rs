@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use serde::{ser, Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+
+/// The `fields=id,name` query parameter of a GET endpoint; list endpoints
+/// read it through `ListOptions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Fields {
+    fields: Option<String>,
+}
+
+impl Fields {
+    pub fn field_set(&self, allowed: &'static [&'static str]) -> AppResult<Option<FieldSet>> {
+        FieldSet::parse(self.fields.as_deref(), allowed)
+    }
+}
+
+/// A sparse fieldset: the fields of a model a client asked for.
+///
+/// Only the requested columns are selected, plus the `id` (which relations
+/// are loaded by) and whatever else the query needs, such as the sort keys
+/// of a cursor. Models are read with `#[sqlx(default)]`, so their other
+/// fields are left at their defaults; they are dropped when the row is
+/// serialized.
+#[derive(Debug, Clone)]
+pub struct FieldSet {
+    /// Every field the model shows, which is also the allowlist: fields the
+    /// API never shows, like password hashes, cannot be requested.
+    allowed: &'static [&'static str],
+    requested: Vec<&'static str>,
+}
+
+impl FieldSet {
+    /// Parses a comma-separated list of fields; `None` when it names none.
+    pub fn parse(raw: Option<&str>, allowed: &'static [&'static str]) -> AppResult<Option<Self>> {
+        let mut requested = Vec::new();
+        let mut unknown = Vec::new();
+        for name in raw.unwrap_or_default().split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+            match allowed.iter().find(|field| **field == name) {
+                Some(field) if !requested.contains(field) => requested.push(*field),
+                Some(_) => {}
+                None => unknown.push(format!("Unknown field: {}", name)),
+            }
+        }
+        if !unknown.is_empty() {
+            return Err(AppError::ValidationError(HashMap::from([(
+                "fields".to_string(),
+                unknown,
+            )])));
+        }
+
+        Ok((!requested.is_empty()).then_some(FieldSet { allowed, requested }))
+    }
+
+    /// The select list: the requested fields, the id and `extra`, in the
+    /// model's declaration order.
+    pub fn select_list(&self, extra: &[&str]) -> String {
+        self.allowed
+            .iter()
+            .filter(|field| {
+                **field == "id" || self.requested.contains(field) || extra.contains(field)
+            })
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Drops the model's fields that were not requested from a serialized
+    /// row. Other keys, such as included relations, are kept.
+    fn retain(&self, row: &mut Value) {
+        if let Value::Object(row) = row {
+            row.retain(|key, _| {
+                !self.allowed.contains(&key.as_str()) || self.requested.contains(&key.as_str())
+            });
+        }
+    }
+
+    /// Serializes `row` with only the requested fields.
+    pub fn serialize_row<T, S>(&self, row: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        let mut row = serde_json::to_value(row).map_err(ser::Error::custom)?;
+        self.retain(&mut row);
+        row.serialize(serializer)
+    }
+}
+
+/// A row that serializes only the fields of `fields`, when given.
+#[derive(Debug)]
+pub struct Sparse<T> {
+    pub row: T,
+    pub fields: Option<FieldSet>,
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.fields {
+            Some(fields) => fields.serialize_row(&self.row, serializer),
+            None => self.row.serialize(serializer),
+        }
+    }
+}
+
+/// Like [`Sparse`], for the rows of a list.
+pub(crate) struct SparseRows<'a, T> {
+    pub rows: &'a [T],
+    pub fields: Option<&'a FieldSet>,
+}
+
+impl<T: Serialize> Serialize for SparseRows<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.fields {
+            Some(fields) => {
+                serializer.collect_seq(self.rows.iter().map(|row| SparseRow { row, fields }))
+            }
+            None => self.rows.serialize(serializer),
+        }
+    }
+}
+
+struct SparseRow<'a, T> {
+    row: &'a T,
+    fields: &'a FieldSet,
+}
+
+impl<T: Serialize> Serialize for SparseRow<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fields.serialize_row(self.row, serializer)
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{PgPool, Row};
+
+use crate::error::{AppError, AppResult};
+
+use super::permission_catalogue::PermissionName;
+use super::repository::ModelRepository;
+use super::List;
+
+/// The `include=contacts,preferred_contact` query parameter, naming the
+/// relations to load along with the requested rows.
+#[derive(Debug, Default, Deserialize)]
+pub struct Include {
+    include: Option<String>,
+}
+
+/// A relation `include=` may name, and the permission needed to see it.
+#[derive(Debug, Clone, Copy)]
+pub struct Relation {
+    pub name: &'static str,
+    pub permission: PermissionName,
+}
+
+impl Include {
+    /// The requested relations of `M`. Unknown names are a validation error;
+    /// relations the caller cannot view, according to `can`, are forbidden.
+    pub fn relations<M: Related>(
+        &self,
+        can: impl Fn(PermissionName) -> bool,
+    ) -> AppResult<Vec<&'static str>> {
+        let mut relations = Vec::new();
+        let mut unknown = Vec::new();
+        for name in self.include.as_deref().unwrap_or_default().split(',') {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            match M::RELATIONS.iter().find(|relation| relation.name == name) {
+                Some(relation) if !can(relation.permission) => return Err(AppError::Forbidden),
+                Some(relation) if !relations.contains(&relation.name) => {
+                    relations.push(relation.name)
+                }
+                Some(_) => {}
+                None => unknown.push(format!("Unknown relation: {}", name)),
+            }
+        }
+        if !unknown.is_empty() {
+            return Err(AppError::ValidationError(HashMap::from([(
+                "include".to_string(),
+                unknown,
+            )])));
+        }
+        Ok(relations)
+    }
+}
+
+/// A row with its included relations, which serialize as extra fields.
+#[derive(Debug, Serialize)]
+pub struct WithRelations<M> {
+    #[serde(flatten)]
+    pub row: M,
+    #[serde(flatten)]
+    pub relations: Map<String, Value>,
+}
+
+#[async_trait]
+pub trait Related: ModelRepository {
+    const RELATIONS: &'static [Relation];
+
+    /// Loads `relation` for all of `rows` with a single query, returning one
+    /// value per row, in order.
+    async fn load_relation(pool: &PgPool, rows: &[Self], relation: &str) -> AppResult<Vec<Value>>;
+}
+
+/// Loads `relations` for `rows`, with one query per relation however many
+/// rows there are.
+pub async fn load<M: Related>(
+    pool: &PgPool,
+    rows: Vec<M>,
+    relations: &[&'static str],
+) -> AppResult<Vec<WithRelations<M>>> {
+    let mut loaded: Vec<Map<String, Value>> = rows.iter().map(|_| Map::new()).collect();
+    for relation in relations {
+        let values = M::load_relation(pool, &rows, relation).await?;
+        for (row, value) in loaded.iter_mut().zip(values) {
+            row.insert(relation.to_string(), value);
+        }
+    }
+
+    Ok(rows
+        .into_iter()
+        .zip(loaded)
+        .map(|(row, relations)| WithRelations { row, relations })
+        .collect())
+}
+
+/// Like [`load`], for a single row.
+pub async fn load_one<M: Related>(
+    pool: &PgPool,
+    row: M,
+    relations: &[&'static str],
+) -> AppResult<WithRelations<M>> {
+    let mut loaded = load(pool, vec![row], relations).await?;
+    Ok(loaded.remove(0))
+}
+
+impl<M: Related> List<M> {
+    /// Loads `relations` for the rows of this page.
+    pub async fn include(
+        self,
+        pool: &PgPool,
+        relations: &[&'static str],
+    ) -> AppResult<List<WithRelations<M>>> {
+        Ok(List {
+            data: load(pool, self.data, relations).await?,
+            highlights: self.highlights,
+            pagination: self.pagination,
+            fields: self.fields,
+        })
+    }
+}
+
+/// A to-many relation: the `R` rows selected by `sql`, which is bound to
+/// `owner_ids` as `$1` and must return each row's owner as `owner_id`.
+///
+/// Relations are always found from the owner's id, which sparse fieldsets
+/// keep, rather than from foreign keys on the owner's row.
+pub async fn has_many<R: ModelRepository>(
+    pool: &PgPool,
+    sql: &str,
+    owner_ids: Vec<i32>,
+) -> AppResult<Vec<Value>> {
+    let mut grouped = grouped::<R>(pool, sql, &owner_ids).await?;
+    Ok(owner_ids
+        .iter()
+        .map(|id| Value::Array(grouped.remove(id).unwrap_or_default()))
+        .collect())
+}
+
+/// Like [`has_many`], for a relation with at most one row, or `null`.
+pub async fn has_one<R: ModelRepository>(
+    pool: &PgPool,
+    sql: &str,
+    owner_ids: Vec<i32>,
+) -> AppResult<Vec<Value>> {
+    let mut grouped = grouped::<R>(pool, sql, &owner_ids).await?;
+    Ok(owner_ids
+        .iter()
+        .map(|id| {
+            grouped
+                .remove(id)
+                .and_then(|rows| rows.into_iter().next())
+                .unwrap_or(Value::Null)
+        })
+        .collect())
+}
+
+async fn grouped<R: ModelRepository>(
+    pool: &PgPool,
+    sql: &str,
+    owner_ids: &[i32],
+) -> AppResult<HashMap<i32, Vec<Value>>> {
+    let mut grouped: HashMap<i32, Vec<Value>> = HashMap::new();
+    for row in sqlx::query(sql).bind(owner_ids).fetch_all(pool).await? {
+        let owner_id: i32 = row.try_get("owner_id")?;
+        grouped
+            .entry(owner_id)
+            .or_default()
+            .push(to_json(&R::from_row(&row)?)?);
+    }
+    Ok(grouped)
+}
+
+/// The error for a relation name the model does not have.
+pub fn unknown_relation(relation: &str) -> AppError {
+    AppError::ValidationError(HashMap::from([(
+        "include".to_string(),
+        vec![format!("Unknown relation: {}", relation)],
+    )]))
+}
+
+fn to_json<T: Serialize>(value: &T) -> AppResult<Value> {
+    serde_json::to_value(value).map_err(|err| sqlx::Error::Encode(Box::new(err)).into())
+}
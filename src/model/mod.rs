@@ -1,17 +1,190 @@
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+use serde::{Serialize, Serializer};
+
+use self::fields::{FieldSet, SparseRows};
+use self::query::{QueryErrors, RawFilter, SearchMode};
+
+pub mod audit_log;
+pub mod contacts;
+pub mod customers;
+pub mod etag;
+pub mod fields;
+pub mod include;
+pub mod password_resets;
+pub mod patch;
+pub mod permission_catalogue;
 pub mod permissions;
+pub mod query;
 pub mod repository;
 pub mod roles;
+pub mod sessions;
 pub mod users;
 
-#[derive(Deserialize)]
+/// Query-string options accepted by every list endpoint.
+///
+/// Besides the scalar options, this accepts `sort=name,-created_at` and any
+/// number of `filter[column]=value` / `filter[column][op]=value` parameters.
+/// Columns are only checked against a model when the list query is built.
+///
+/// `pagination=cursor` (or passing a `cursor`) switches from page numbers to
+/// keyset pagination, and `count=false` skips the `COUNT(*)` query.
+/// Archived rows of soft-deleted models are hidden unless `with_archived=true`
+/// or `only_archived=true` is passed.
+///
+/// `search=fulltext` matches `q` word by word instead of as a substring,
+/// ranks the results by relevance and adds highlighted fragments.
+///
+/// `fields=id,name` selects only those columns; see [`FieldSet`].
+#[derive(Debug, Clone, Default)]
 pub struct ListOptions {
     pub q: Option<String>,
-    pub page: Option<u16>,
+    pub page: Option<u64>,
     pub per_page: Option<u16>,
     pub sort_by: Option<String>,
     pub ascending: Option<bool>,
+    pub sort: Option<String>,
+    pub filters: Vec<RawFilter>,
+    pub pagination: Option<PaginationMode>,
+    pub cursor: Option<String>,
+    pub count: Option<bool>,
+    pub with_archived: Option<bool>,
+    pub only_archived: Option<bool>,
+    pub search: Option<SearchMode>,
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaginationMode {
+    Offset,
+    Cursor,
+}
+
+impl ListOptions {
+    /// The requested sort, falling back to the older `sort_by`/`ascending` pair.
+    pub fn sort_spec(&self) -> String {
+        match (&self.sort, &self.sort_by) {
+            (Some(sort), _) => sort.clone(),
+            (None, Some(sort_by)) if self.ascending == Some(false) => format!("-{}", sort_by),
+            (None, Some(sort_by)) => sort_by.clone(),
+            (None, None) => "id".to_string(),
+        }
+    }
+
+    pub fn uses_cursor(&self) -> bool {
+        match self.pagination {
+            Some(mode) => mode == PaginationMode::Cursor,
+            None => self.cursor.is_some(),
+        }
+    }
+
+    /// The `IS NULL` test to apply to a model's archive column, if any.
+    pub fn archived_is_null(&self) -> Option<bool> {
+        if self.only_archived == Some(true) {
+            Some(false)
+        } else if self.with_archived == Some(true) {
+            None
+        } else {
+            Some(true)
+        }
+    }
+
+    pub fn search_mode(&self) -> SearchMode {
+        self.search.unwrap_or_default()
+    }
+
+    /// The page size, 10 by default and clamped to 10..=1000.
+    pub fn page_size(&self) -> u16 {
+        self.per_page.unwrap_or(10).clamp(10, 1000)
+    }
+
+    /// Whether the exact total should be counted; defaults to true.
+    pub fn wants_count(&self) -> bool {
+        self.count.unwrap_or(true)
+    }
+
+    /// Reads the options from the query string's key/value pairs, keeping
+    /// repeated and bracketed `filter[...]` keys. Bad values are reported
+    /// under their parameter; unknown parameters are ignored.
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> Result<Self, QueryErrors> {
+        let mut options = ListOptions::default();
+        let mut errors = QueryErrors::new();
+        for (key, value) in pairs {
+            if let Err(message) = options.set(&key, value) {
+                errors.entry(key).or_default().push(message);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(options)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn set(&mut self, key: &str, value: String) -> Result<(), String> {
+        match key {
+            "q" => self.q = Some(value),
+            "page" => self.page = Some(parse_param(&value, "Expected a whole number.")?),
+            "per_page" => self.per_page = Some(parse_param(&value, "Expected a whole number.")?),
+            "sort_by" => self.sort_by = Some(value),
+            "ascending" => self.ascending = Some(parse_bool_param(&value)?),
+            "sort" => self.sort = Some(value),
+            "pagination" => {
+                self.pagination = Some(match value.as_str() {
+                    "offset" => PaginationMode::Offset,
+                    "cursor" => PaginationMode::Cursor,
+                    _ => return Err("Expected offset or cursor.".to_string()),
+                })
+            }
+            "cursor" => self.cursor = Some(value),
+            "fields" => self.fields = Some(value),
+            "count" => self.count = Some(parse_bool_param(&value)?),
+            "with_archived" => self.with_archived = Some(parse_bool_param(&value)?),
+            "only_archived" => self.only_archived = Some(parse_bool_param(&value)?),
+            "search" => {
+                self.search = Some(match value.as_str() {
+                    "contains" => SearchMode::Contains,
+                    "fulltext" => SearchMode::FullText,
+                    _ => return Err("Expected contains or fulltext.".to_string()),
+                })
+            }
+            _ => {
+                if let Some(filter) = key.strip_prefix("filter[") {
+                    self.filters.push(parse_filter_key(filter, value)?);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_param<T: std::str::FromStr>(value: &str, message: &str) -> Result<T, String> {
+    value.parse().map_err(|_| message.to_string())
+}
+
+fn parse_bool_param(value: &str) -> Result<bool, String> {
+    parse_param(value, "Expected true or false.")
+}
+
+/// Parses the remainder of `filter[column]` or `filter[column][op]`.
+fn parse_filter_key(rest: &str, value: String) -> Result<RawFilter, String> {
+    let invalid = || "Expected filter[column] or filter[column][op].".to_string();
+    let rest = rest.strip_suffix(']').ok_or_else(invalid)?;
+
+    let (column, op) = match rest.split_once("][") {
+        Some((column, op)) => (column, Some(op.to_string())),
+        None => (rest, None),
+    };
+    if column.is_empty() || column.contains(['[', ']']) {
+        return Err(invalid());
+    }
+
+    Ok(RawFilter {
+        column: column.to_string(),
+        op,
+        value,
+    })
 }
 
 //#[async_trait]
@@ -80,16 +253,114 @@ pub struct ListOptions {
 //    fn table_name() -> &'static str;
 //}
 
-#[derive(Serialize)]
 pub struct List<T> {
     pub data: Vec<T>,
+    /// With `search=fulltext`, the matched search columns of each row in
+    /// `data`, in the same order, with matches wrapped in `<mark>` tags.
+    pub highlights: Option<Vec<HashMap<String, String>>>,
     pub pagination: Paginator,
+    /// With `fields=`, the fields the rows in `data` were selected with;
+    /// only those are serialized.
+    pub fields: Option<FieldSet>,
+}
+
+impl<T: Serialize> Serialize for List<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Page<'a, T: Serialize> {
+            data: SparseRows<'a, T>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            highlights: Option<&'a Vec<HashMap<String, String>>>,
+            pagination: &'a Paginator,
+        }
+
+        Page {
+            data: SparseRows {
+                rows: &self.data,
+                fields: self.fields.as_ref(),
+            },
+            highlights: self.highlights.as_ref(),
+            pagination: &self.pagination,
+        }
+        .serialize(serializer)
+    }
 }
 
+/// Page metadata. Page numbers are only set in offset mode, cursors only in
+/// cursor mode, and totals are omitted when `count=false`.
 #[derive(Serialize)]
 pub struct Paginator {
-    pub current_page: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_page: Option<u64>,
     pub per_page: u16,
-    pub total_pages: u16,
-    pub total_count: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn from_pairs_reads_options_and_filters() {
+        let options = ListOptions::from_pairs(pairs(&[
+            ("page", "2"),
+            ("per_page", "50"),
+            ("search", "fulltext"),
+            ("filter[name]", "Acme"),
+            ("filter[id][in]", "1,2"),
+            ("unknown", "ignored"),
+        ]))
+        .unwrap();
+
+        assert_eq!(options.page, Some(2));
+        assert_eq!(options.per_page, Some(50));
+        assert_eq!(options.search, Some(SearchMode::FullText));
+        assert_eq!(
+            options.filters,
+            [
+                RawFilter {
+                    column: "name".to_string(),
+                    op: None,
+                    value: "Acme".to_string(),
+                },
+                RawFilter {
+                    column: "id".to_string(),
+                    op: Some("in".to_string()),
+                    value: "1,2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn from_pairs_reports_every_bad_parameter() {
+        let errors = ListOptions::from_pairs(pairs(&[
+            ("page", "-1"),
+            ("count", "maybe"),
+            ("pagination", "pages"),
+            ("filter[name", "x"),
+            ("filter[][eq]", "x"),
+        ]))
+        .unwrap_err();
+
+        assert_eq!(errors["page"], ["Expected a whole number."]);
+        assert_eq!(errors["count"], ["Expected true or false."]);
+        assert_eq!(errors["pagination"], ["Expected offset or cursor."]);
+        assert!(errors.contains_key("filter[name"));
+        assert!(errors.contains_key("filter[][eq]"));
+    }
 }
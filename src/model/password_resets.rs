@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use sqlx::{PgExecutor, PgPool};
+
+use crate::{
+    auth::tokens::{expires_at, hash_token, random_token},
+    error::AppResult,
+};
+
+/// A password reset token, as issued to the user it was issued for. Only
+/// its hash is stored.
+#[derive(Debug)]
+pub struct PasswordReset {
+    pub user_id: i32,
+    pub name: String,
+    pub email: String,
+    pub token: String,
+}
+
+impl PasswordReset {
+    /// Issues a reset token for the user with `email`, replacing any earlier
+    /// one; `None` when no user has that email.
+    pub async fn issue(
+        pool: &PgPool,
+        email: &str,
+        lifetime: Duration,
+    ) -> AppResult<Option<PasswordReset>> {
+        let token = random_token();
+        let user: Option<(i32, String)> = sqlx::query_as(
+            "WITH target AS (SELECT id, name FROM users WHERE email = $1), \
+             replaced AS ( \
+                 DELETE FROM password_resets WHERE user_id IN (SELECT id FROM target) \
+             ) \
+             INSERT INTO password_resets (token_hash, user_id, expires_at) \
+             SELECT $2, id, $3 FROM target \
+             RETURNING user_id, (SELECT name FROM target)",
+        )
+        .bind(email)
+        .bind(hash_token(&token))
+        .bind(expires_at(lifetime))
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(user.map(|(user_id, name)| PasswordReset {
+            user_id,
+            name,
+            email: email.to_string(),
+            token,
+        }))
+    }
+
+    /// Marks `token` as used, returning the user it resets the password of.
+    /// `None` when the token is unknown, expired or already used.
+    pub async fn consume<'e, E: PgExecutor<'e>>(
+        executor: E,
+        token: &str,
+    ) -> AppResult<Option<i32>> {
+        Ok(sqlx::query_scalar(
+            "UPDATE password_resets SET used_at = now() \
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now() \
+             RETURNING user_id",
+        )
+        .bind(hash_token(token))
+        .fetch_optional(executor)
+        .await?)
+    }
+}
@@ -0,0 +1,241 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{Postgres, QueryBuilder};
+use validator::{ValidateEmail, ValidateLength, ValidateRange, ValidationError};
+
+use super::query::SqlValue;
+
+/// A field of a partial update: left out of the payload, explicitly `null`,
+/// or given a value.
+///
+/// Fields must be marked `#[serde(default)]` so that a missing key
+/// deserializes as [`Patch::Absent`] rather than [`Patch::Null`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Patch::Absent)
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Patch::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Absent => Patch::Absent,
+            Patch::Null => Patch::Null,
+            Patch::Value(value) => Patch::Value(f(value)),
+        }
+    }
+}
+
+/// A present `Option`: `None` clears the column, as a full update would.
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Patch::Value(value),
+            None => Patch::Null,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Patch::from)
+    }
+}
+
+impl<T: Serialize> Serialize for Patch<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value().serialize(serializer)
+    }
+}
+
+impl<T: ValidateLength<u64>> ValidateLength<u64> for Patch<T> {
+    fn length(&self) -> Option<u64> {
+        self.value().and_then(T::length)
+    }
+}
+
+impl<T: ValidateEmail> ValidateEmail for Patch<T> {
+    fn as_email_string(&self) -> Option<Cow<'_, str>> {
+        self.value().and_then(T::as_email_string)
+    }
+}
+
+impl<R, T: ValidateRange<R>> ValidateRange<R> for Patch<T> {
+    fn greater_than(&self, max: R) -> Option<bool> {
+        self.value().and_then(|value| value.greater_than(max))
+    }
+
+    fn less_than(&self, min: R) -> Option<bool> {
+        self.value().and_then(|value| value.less_than(min))
+    }
+}
+
+/// Custom validator for columns that may be left out but never cleared.
+pub fn not_null<T>(value: &Patch<T>) -> Result<(), ValidationError> {
+    match value {
+        Patch::Null => Err(ValidationError::new("not_null")),
+        _ => Ok(()),
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(value: i32) -> Self {
+        SqlValue::Int(value)
+    }
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<DateTime<Utc>> for SqlValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        SqlValue::Timestamp(value)
+    }
+}
+
+/// The columns an update writes, in order. Absent fields are never added, so
+/// `UPDATE` only touches what the caller actually sent.
+#[derive(Debug, Default)]
+pub struct Changeset {
+    changes: Vec<(&'static str, Option<SqlValue>)>,
+}
+
+impl Changeset {
+    pub fn new() -> Self {
+        Changeset::default()
+    }
+
+    pub fn patch<T: Into<SqlValue>>(mut self, column: &'static str, value: Patch<T>) -> Self {
+        match value {
+            Patch::Absent => {}
+            Patch::Null => self.changes.push((column, None)),
+            Patch::Value(value) => self.changes.push((column, Some(value.into()))),
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.changes.iter().map(|(column, _)| *column)
+    }
+
+    /// Pushes `col = $n, ...` for a `SET` clause.
+    pub fn push_set(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        for (i, (column, value)) in self.changes.iter().enumerate() {
+            if i > 0 {
+                builder.push(", ");
+            }
+            builder.push(*column).push(" = ");
+            match value {
+                Some(value) => value.push_bind(builder),
+                None => {
+                    builder.push("NULL");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Payload {
+        #[serde(default)]
+        #[validate(length(max = 5), custom(function = "not_null"))]
+        name: Patch<String>,
+        #[serde(default)]
+        credit_limit: Patch<i32>,
+    }
+
+    fn payload(value: serde_json::Value) -> Payload {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn deserializes_absent_null_and_value() {
+        let absent = payload(json!({}));
+        assert_eq!(absent.name, Patch::Absent);
+        assert_eq!(absent.credit_limit, Patch::Absent);
+
+        let null = payload(json!({ "name": null, "credit_limit": null }));
+        assert_eq!(null.name, Patch::Null);
+        assert_eq!(null.credit_limit, Patch::Null);
+
+        let value = payload(json!({ "name": "Acme", "credit_limit": 100 }));
+        assert_eq!(value.name, Patch::Value("Acme".to_string()));
+        assert_eq!(value.credit_limit, Patch::Value(100));
+    }
+
+    #[test]
+    fn rejects_a_mistyped_value() {
+        assert!(serde_json::from_value::<Payload>(json!({ "credit_limit": "lots" })).is_err());
+    }
+
+    #[test]
+    fn validates_only_values_and_refuses_null_when_asked() {
+        assert!(payload(json!({})).validate().is_ok());
+        assert!(payload(json!({ "name": "Acme" })).validate().is_ok());
+
+        let errors = payload(json!({ "name": "Acme Pty Ltd" }))
+            .validate()
+            .unwrap_err();
+        assert_eq!(errors.field_errors()["name"][0].code, "length");
+
+        let errors = payload(json!({ "name": null })).validate().unwrap_err();
+        assert_eq!(errors.field_errors()["name"][0].code, "not_null");
+    }
+
+    #[test]
+    fn changeset_skips_absent_fields() {
+        let changes = Changeset::new()
+            .patch("name", Patch::Value("Acme".to_string()))
+            .patch("credit_limit", Patch::<i32>::Null)
+            .patch("active", Patch::<bool>::Absent);
+        assert_eq!(
+            changes.columns().collect::<Vec<_>>(),
+            ["name", "credit_limit"]
+        );
+
+        let mut builder = QueryBuilder::new("");
+        changes.push_set(&mut builder);
+        assert_eq!(builder.sql(), "name = $1, credit_limit = NULL");
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::error::AppResult;
+
+/// Every permission the application checks. The `permissions` table is
+/// synchronised from this list by [`sync`]; handlers never use free-form names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PermissionName {
+    UsersView,
+    UsersCreate,
+    UsersUpdate,
+    UsersDelete,
+    CustomersView,
+    CustomersCreate,
+    CustomersUpdate,
+    CustomersDelete,
+    CustomersPurge,
+    ContactsView,
+    ContactsCreate,
+    ContactsUpdate,
+    ContactsDelete,
+    RolesView,
+    RolesCreate,
+    RolesUpdate,
+    RolesDelete,
+    RolesAssign,
+    PermissionsView,
+    AuditLogView,
+}
+
+impl PermissionName {
+    pub const ALL: &'static [PermissionName] = &[
+        PermissionName::UsersView,
+        PermissionName::UsersCreate,
+        PermissionName::UsersUpdate,
+        PermissionName::UsersDelete,
+        PermissionName::CustomersView,
+        PermissionName::CustomersCreate,
+        PermissionName::CustomersUpdate,
+        PermissionName::CustomersDelete,
+        PermissionName::CustomersPurge,
+        PermissionName::ContactsView,
+        PermissionName::ContactsCreate,
+        PermissionName::ContactsUpdate,
+        PermissionName::ContactsDelete,
+        PermissionName::RolesView,
+        PermissionName::RolesCreate,
+        PermissionName::RolesUpdate,
+        PermissionName::RolesDelete,
+        PermissionName::RolesAssign,
+        PermissionName::PermissionsView,
+        PermissionName::AuditLogView,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PermissionName::UsersView => "users.view",
+            PermissionName::UsersCreate => "users.create",
+            PermissionName::UsersUpdate => "users.update",
+            PermissionName::UsersDelete => "users.delete",
+            PermissionName::CustomersView => "customers.view",
+            PermissionName::CustomersCreate => "customers.create",
+            PermissionName::CustomersUpdate => "customers.update",
+            PermissionName::CustomersDelete => "customers.delete",
+            PermissionName::CustomersPurge => "customers.purge",
+            PermissionName::ContactsView => "contacts.view",
+            PermissionName::ContactsCreate => "contacts.create",
+            PermissionName::ContactsUpdate => "contacts.update",
+            PermissionName::ContactsDelete => "contacts.delete",
+            PermissionName::RolesView => "roles.view",
+            PermissionName::RolesCreate => "roles.create",
+            PermissionName::RolesUpdate => "roles.update",
+            PermissionName::RolesDelete => "roles.delete",
+            PermissionName::RolesAssign => "roles.assign",
+            PermissionName::PermissionsView => "permissions.view",
+            PermissionName::AuditLogView => "audit_log.view",
+        }
+    }
+}
+
+impl fmt::Display for PermissionName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub const ADMIN_ROLE: &str = "admin";
+pub const STAFF_ROLE: &str = "staff";
+pub const CUSTOMER_ROLE: &str = "customer";
+
+/// Roles created by [`sync`] when they do not exist yet.
+pub struct DefaultRole {
+    pub name: &'static str,
+    pub grants: Grants,
+}
+
+pub enum Grants {
+    /// Every permission in the catalogue, kept up to date on each sync.
+    All,
+    /// Granted once, when the role is first created.
+    Only(&'static [PermissionName]),
+}
+
+pub const DEFAULT_ROLES: &[DefaultRole] = &[
+    DefaultRole {
+        name: ADMIN_ROLE,
+        grants: Grants::All,
+    },
+    DefaultRole {
+        name: STAFF_ROLE,
+        grants: Grants::Only(&[
+            PermissionName::UsersView,
+            PermissionName::CustomersView,
+            PermissionName::CustomersCreate,
+            PermissionName::CustomersUpdate,
+            PermissionName::CustomersDelete,
+            PermissionName::ContactsView,
+            PermissionName::ContactsCreate,
+            PermissionName::ContactsUpdate,
+            PermissionName::ContactsDelete,
+        ]),
+    },
+    DefaultRole {
+        name: CUSTOMER_ROLE,
+        grants: Grants::Only(&[PermissionName::CustomersView, PermissionName::ContactsView]),
+    },
+];
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    /// Catalogue permissions that were missing from the database.
+    pub inserted: Vec<String>,
+    /// Database permissions no longer declared in the catalogue.
+    pub orphaned: Vec<String>,
+    /// Default roles that did not exist and were created.
+    pub created_roles: Vec<String>,
+}
+
+/// Brings the `permissions` table and default roles in line with the catalogue.
+///
+/// Orphaned permissions are reported, not deleted, so that grants an operator
+/// still relies on are never silently dropped.
+pub async fn sync(pool: &PgPool) -> AppResult<SyncReport> {
+    let names: Vec<String> = PermissionName::ALL
+        .iter()
+        .map(|name| name.as_str().to_string())
+        .collect();
+
+    let mut tx = pool.begin().await?;
+
+    let inserted: Vec<String> = sqlx::query_scalar(
+        "INSERT INTO permissions (name) SELECT unnest($1::VARCHAR[]) \
+         ON CONFLICT (name) DO NOTHING \
+         RETURNING name",
+    )
+    .bind(&names)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let orphaned: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM permissions WHERE name <> ALL($1::VARCHAR[]) ORDER BY name",
+    )
+    .bind(&names)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut created_roles = Vec::new();
+    for role in DEFAULT_ROLES {
+        let created: Option<i32> = sqlx::query_scalar(
+            "INSERT INTO roles (name) VALUES ($1) ON CONFLICT (name) DO NOTHING RETURNING id",
+        )
+        .bind(role.name)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let grants: Vec<String> = match (&role.grants, created) {
+            (Grants::All, _) => names.clone(),
+            (Grants::Only(permissions), Some(_)) => permissions
+                .iter()
+                .map(|name| name.as_str().to_string())
+                .collect(),
+            (Grants::Only(_), None) => continue,
+        };
+
+        sqlx::query(
+            "INSERT INTO role_has_permissions (role_id, permission_id) \
+             SELECT r.id, p.id FROM roles r, permissions p \
+             WHERE r.name = $1 AND p.name = ANY($2::VARCHAR[]) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(role.name)
+        .bind(&grants)
+        .execute(&mut *tx)
+        .await?;
+
+        if created.is_some() {
+            created_roles.push(role.name.to_string());
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(SyncReport {
+        inserted,
+        orphaned,
+        created_roles,
+    })
+}
@@ -1,11 +1,18 @@
+use asg_macros::ModelRepository;
 use serde::Serialize;
-use sqlx::prelude::FromRow;
+use sqlx::{prelude::FromRow, PgPool};
 
-use super::repository::{ModelRepository, PgQuery};
+use crate::error::AppResult;
 
-#[derive(Serialize, Debug, FromRow)]
+use super::patch::Patch;
+
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(table = "permissions", create = PermissionForCreate, update = PermissionForUpdate)]
 pub struct Permission {
+    #[model(indexed)]
     pub id: i32,
+    #[model(create, update, search, indexed)]
     pub name: String,
 }
 
@@ -13,28 +20,37 @@ pub struct PermissionForCreate {
     pub name: String,
 }
 
-impl ModelRepository for Permission {
-    type CreateModel = PermissionForCreate;
-
-    type UpdateModel = PermissionForCreate;
-
-    const TABLE_NAME: &'static str = "permissions";
-
-    const CREATE_FIELDS: &'static [&'static str] = &["name"];
-
-    const UPDATE_FIELDS: &'static [&'static str] = &["name"];
-
-    const SEARCH_COLUMNS: &'static [&'static str] = &["name"];
+#[derive(Default)]
+pub struct PermissionForUpdate {
+    pub name: Patch<String>,
+}
 
-    fn bind_create(query: PgQuery<'_, Self>, data: Self::CreateModel) -> PgQuery<'_, Self> {
-        query.bind(data.name)
+impl Permission {
+    /// Names of every permission the user holds through any of their roles.
+    pub async fn names_for_user(pool: &PgPool, user_id: i32) -> AppResult<Vec<String>> {
+        let names = sqlx::query_scalar(
+            "SELECT DISTINCT p.name FROM permissions p \
+             JOIN role_has_permissions rp ON rp.permission_id = p.id \
+             JOIN user_has_roles ur ON ur.role_id = rp.role_id \
+             WHERE ur.user_id = $1 \
+             ORDER BY p.name",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(names)
     }
 
-    fn bind_update(
-        query: PgQuery<'_, Self>,
-        id: i32,
-        data: Self::UpdateModel,
-    ) -> PgQuery<'_, Self> {
-        query.bind(id).bind(data.name)
+    pub async fn for_role(pool: &PgPool, role_id: i32) -> AppResult<Vec<Self>> {
+        let permissions = sqlx::query_as::<_, Self>(
+            "SELECT p.* FROM permissions p \
+             JOIN role_has_permissions rp ON rp.permission_id = p.id \
+             WHERE rp.role_id = $1 \
+             ORDER BY p.name",
+        )
+        .bind(role_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(permissions)
     }
 }
@@ -0,0 +1,993 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Postgres, QueryBuilder, Row};
+
+/// Errors keyed by the offending query parameter, e.g. `filter[name][gt]`.
+pub type QueryErrors = HashMap<String, Vec<String>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Int,
+    BigInt,
+    Bool,
+    Timestamp,
+}
+
+/// A column a model exposes to list queries. Columns not declared here can
+/// never be sorted or filtered on, which keeps user input out of the SQL.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+    pub nullable: bool,
+    pub sortable: bool,
+    pub filterable: bool,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, kind: ColumnKind) -> Self {
+        Column {
+            name,
+            kind,
+            nullable: false,
+            sortable: false,
+            filterable: false,
+        }
+    }
+
+    pub const fn text(name: &'static str) -> Self {
+        Column::new(name, ColumnKind::Text)
+    }
+
+    pub const fn int(name: &'static str) -> Self {
+        Column::new(name, ColumnKind::Int)
+    }
+
+    pub const fn bigint(name: &'static str) -> Self {
+        Column::new(name, ColumnKind::BigInt)
+    }
+
+    pub const fn bool(name: &'static str) -> Self {
+        Column::new(name, ColumnKind::Bool)
+    }
+
+    pub const fn timestamp(name: &'static str) -> Self {
+        Column::new(name, ColumnKind::Timestamp)
+    }
+
+    pub const fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub const fn sortable(mut self) -> Self {
+        self.sortable = true;
+        self
+    }
+
+    pub const fn filterable(mut self) -> Self {
+        self.filterable = true;
+        self
+    }
+
+    /// Shorthand for a column that is both sortable and filterable.
+    pub const fn indexed(self) -> Self {
+        self.sortable().filterable()
+    }
+}
+
+/// A typed value bound into a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Text(String),
+    Int(i32),
+    BigInt(i64),
+    Bool(bool),
+    Timestamp(DateTime<Utc>),
+    TextList(Vec<String>),
+    IntList(Vec<i32>),
+    BigIntList(Vec<i64>),
+    BoolList(Vec<bool>),
+    TimestampList(Vec<DateTime<Utc>>),
+}
+
+impl SqlValue {
+    pub fn push_bind(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            SqlValue::Text(value) => builder.push_bind(value.clone()),
+            SqlValue::Int(value) => builder.push_bind(*value),
+            SqlValue::BigInt(value) => builder.push_bind(*value),
+            SqlValue::Bool(value) => builder.push_bind(*value),
+            SqlValue::Timestamp(value) => builder.push_bind(*value),
+            SqlValue::TextList(values) => builder.push_bind(values.clone()),
+            SqlValue::IntList(values) => builder.push_bind(values.clone()),
+            SqlValue::BigIntList(values) => builder.push_bind(values.clone()),
+            SqlValue::BoolList(values) => builder.push_bind(values.clone()),
+            SqlValue::TimestampList(values) => builder.push_bind(values.clone()),
+        };
+    }
+
+    /// Parses a single query-string value as the column's type.
+    pub fn parse(kind: ColumnKind, raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        match kind {
+            ColumnKind::Text => Ok(SqlValue::Text(raw.to_string())),
+            ColumnKind::Int => parse_int(raw).map(SqlValue::Int),
+            ColumnKind::BigInt => parse_int(raw).map(SqlValue::BigInt),
+            ColumnKind::Bool => parse_bool(raw)
+                .map(SqlValue::Bool)
+                .ok_or_else(|| "Expected true or false.".to_string()),
+            ColumnKind::Timestamp => parse_timestamp(raw).map(SqlValue::Timestamp),
+        }
+    }
+
+    /// Parses a comma-separated list as the column's type.
+    pub fn parse_list(kind: ColumnKind, raw: &str) -> Result<Self, String> {
+        let items = raw.split(',').map(str::trim);
+        match kind {
+            ColumnKind::Text => Ok(SqlValue::TextList(items.map(str::to_string).collect())),
+            ColumnKind::Int => items
+                .map(parse_int)
+                .collect::<Result<_, _>>()
+                .map(SqlValue::IntList),
+            ColumnKind::BigInt => items
+                .map(parse_int)
+                .collect::<Result<_, _>>()
+                .map(SqlValue::BigIntList),
+            ColumnKind::Bool => items
+                .map(|item| parse_bool(item).ok_or_else(|| "Expected true or false.".to_string()))
+                .collect::<Result<_, _>>()
+                .map(SqlValue::BoolList),
+            ColumnKind::Timestamp => items
+                .map(parse_timestamp)
+                .collect::<Result<_, _>>()
+                .map(SqlValue::TimestampList),
+        }
+    }
+}
+
+fn parse_int<T: std::str::FromStr>(raw: &str) -> Result<T, String> {
+    raw.parse().map_err(|_| "Expected an integer.".to_string())
+}
+
+fn parse_bool(raw: &str) -> Option<bool> {
+    match raw.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Accepts RFC 3339, or a bare date meaning midnight UTC.
+fn parse_timestamp(raw: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+        .ok_or_else(|| "Expected a date (YYYY-MM-DD) or RFC 3339 timestamp.".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Neq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+    IsNull,
+}
+
+impl FilterOp {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "eq" => Some(FilterOp::Eq),
+            "neq" => Some(FilterOp::Neq),
+            "gt" => Some(FilterOp::Gt),
+            "gte" => Some(FilterOp::Gte),
+            "lt" => Some(FilterOp::Lt),
+            "lte" => Some(FilterOp::Lte),
+            "in" => Some(FilterOp::In),
+            "null" => Some(FilterOp::IsNull),
+            _ => None,
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            FilterOp::Eq => " = ",
+            FilterOp::Neq => " <> ",
+            FilterOp::Gt => " > ",
+            FilterOp::Gte => " >= ",
+            FilterOp::Lt => " < ",
+            FilterOp::Lte => " <= ",
+            FilterOp::In => " = ANY(",
+            FilterOp::IsNull => " IS NULL",
+        }
+    }
+}
+
+/// A filter as written in the query string: `filter[column][op]=value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawFilter {
+    pub column: String,
+    pub op: Option<String>,
+    pub value: String,
+}
+
+impl RawFilter {
+    fn param(&self) -> String {
+        match &self.op {
+            Some(op) => format!("filter[{}][{}]", self.column, op),
+            None => format!("filter[{}]", self.column),
+        }
+    }
+}
+
+/// A validated `WHERE` condition on a whitelisted column.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Compare {
+        column: &'static str,
+        op: FilterOp,
+        value: SqlValue,
+    },
+    Null {
+        column: &'static str,
+        is_null: bool,
+    },
+}
+
+impl Condition {
+    pub fn eq(column: &'static str, value: SqlValue) -> Self {
+        Condition::Compare {
+            column,
+            op: FilterOp::Eq,
+            value,
+        }
+    }
+
+    pub fn push(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            Condition::Compare { column, op, value } => {
+                builder.push(column).push(op.sql());
+                value.push_bind(builder);
+                if *op == FilterOp::In {
+                    builder.push(")");
+                }
+            }
+            Condition::Null { column, is_null } => {
+                builder
+                    .push(column)
+                    .push(if *is_null { " IS NULL" } else { " IS NOT NULL" });
+            }
+        }
+    }
+}
+
+/// Validates raw filters against a model's columns.
+pub fn parse_filters(
+    columns: &[Column],
+    filters: &[RawFilter],
+) -> Result<Vec<Condition>, QueryErrors> {
+    let mut errors = QueryErrors::new();
+    let mut conditions = Vec::new();
+
+    for filter in filters {
+        let param = filter.param();
+        let Some(column) = columns
+            .iter()
+            .find(|column| column.filterable && column.name == filter.column)
+        else {
+            errors.entry(param).or_default().push(format!(
+                "Cannot filter by unknown column `{}`.",
+                filter.column
+            ));
+            continue;
+        };
+
+        let op = match filter.op.as_deref() {
+            None => FilterOp::Eq,
+            Some(raw) => match FilterOp::parse(raw) {
+                Some(op) => op,
+                None => {
+                    errors.entry(param).or_default().push(format!(
+                        "Unknown operator `{}`. Use eq, neq, gt, gte, lt, lte, in or null.",
+                        raw
+                    ));
+                    continue;
+                }
+            },
+        };
+
+        let condition = match op {
+            FilterOp::IsNull => match parse_bool(filter.value.trim()) {
+                Some(is_null) if column.nullable => Ok(Condition::Null {
+                    column: column.name,
+                    is_null,
+                }),
+                Some(_) => Err(format!("Column `{}` is never null.", column.name)),
+                None => Err("Expected true or false.".to_string()),
+            },
+            FilterOp::In => {
+                SqlValue::parse_list(column.kind, &filter.value).map(|value| Condition::Compare {
+                    column: column.name,
+                    op,
+                    value,
+                })
+            }
+            FilterOp::Gt | FilterOp::Gte | FilterOp::Lt | FilterOp::Lte
+                if column.kind == ColumnKind::Bool =>
+            {
+                Err("Booleans can only be compared with eq or neq.".to_string())
+            }
+            _ => SqlValue::parse(column.kind, &filter.value).map(|value| Condition::Compare {
+                column: column.name,
+                op,
+                value,
+            }),
+        };
+
+        match condition {
+            Ok(condition) => conditions.push(condition),
+            Err(message) => errors.entry(param).or_default().push(message),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(conditions)
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl SortKey {
+    pub fn direction(&self) -> &'static str {
+        if self.descending {
+            "DESC"
+        } else {
+            "ASC"
+        }
+    }
+
+    pub fn reversed(self) -> Self {
+        SortKey {
+            descending: !self.descending,
+            ..self
+        }
+    }
+}
+
+/// Validates a `sort=name,-created_at` list against a model's columns.
+///
+/// `id` is always appended as a tie-breaker so that pages are stable.
+pub fn parse_sort(columns: &[Column], sort: &str) -> Result<Vec<SortKey>, QueryErrors> {
+    let mut errors = Vec::new();
+    let mut keys: Vec<SortKey> = Vec::new();
+
+    for raw in sort.split(',').map(str::trim).filter(|raw| !raw.is_empty()) {
+        let (name, descending) = match raw.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (raw.strip_prefix('+').unwrap_or(raw), false),
+        };
+
+        match columns
+            .iter()
+            .find(|column| column.sortable && column.name == name)
+        {
+            Some(column) if keys.iter().any(|key| key.column.name == column.name) => {
+                errors.push(format!("Column `{}` is sorted on more than once.", name))
+            }
+            Some(column) => keys.push(SortKey {
+                column: *column,
+                descending,
+            }),
+            None => errors.push(format!("Cannot sort by unknown column `{}`.", name)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(QueryErrors::from([("sort".to_string(), errors)]));
+    }
+
+    if !keys.iter().any(|key| key.column.name == "id") {
+        keys.push(SortKey {
+            column: Column::int("id"),
+            descending: false,
+        });
+    }
+
+    Ok(keys)
+}
+
+pub fn push_order_by(builder: &mut QueryBuilder<'_, Postgres>, keys: &[SortKey]) {
+    builder.push(" ORDER BY ");
+    let mut separated = builder.separated(", ");
+    for key in keys {
+        separated.push(format!("{} {}", key.column.name, key.direction()));
+    }
+}
+
+/// Orders by relevance first, then by the requested sort.
+pub fn push_ranked_order_by(builder: &mut QueryBuilder<'_, Postgres>, keys: &[SortKey]) {
+    builder.push(" ORDER BY search_rank DESC");
+    for key in keys {
+        builder.push(format!(", {} {}", key.column.name, key.direction()));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorDirection {
+    #[serde(rename = "n")]
+    Next,
+    #[serde(rename = "p")]
+    Prev,
+}
+
+/// Opaque keyset pagination cursor: the sort key values of the row at the
+/// edge of a page, plus the sort it was issued for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(rename = "s")]
+    pub sort: String,
+    #[serde(rename = "d")]
+    pub direction: CursorDirection,
+    #[serde(rename = "v")]
+    pub values: Vec<serde_json::Value>,
+}
+
+impl Cursor {
+    /// Builds a cursor from a serialized row at the edge of a page.
+    pub fn from_row(keys: &[SortKey], direction: CursorDirection, row: &serde_json::Value) -> Self {
+        Cursor {
+            sort: sort_signature(keys),
+            direction,
+            values: keys
+                .iter()
+                .map(|key| row.get(key.column.name).cloned().unwrap_or_default())
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(raw: &str) -> Option<Self> {
+        let json = general_purpose::URL_SAFE_NO_PAD.decode(raw).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    /// The typed boundary values, if the cursor was issued for `keys`.
+    pub fn boundary(&self, keys: &[SortKey]) -> Result<Vec<Option<SqlValue>>, String> {
+        if self.sort != sort_signature(keys) || self.values.len() != keys.len() {
+            return Err("The cursor does not match the requested sort.".to_string());
+        }
+
+        keys.iter()
+            .zip(&self.values)
+            .map(|(key, value)| json_to_sql(key.column, value))
+            .collect::<Option<_>>()
+            .ok_or_else(|| "Invalid cursor.".to_string())
+    }
+}
+
+/// Canonical form of a sort, e.g. `name,-created_at,id`.
+pub fn sort_signature(keys: &[SortKey]) -> String {
+    keys.iter()
+        .map(|key| {
+            if key.descending {
+                format!("-{}", key.column.name)
+            } else {
+                key.column.name.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn json_to_sql(column: Column, value: &serde_json::Value) -> Option<Option<SqlValue>> {
+    if value.is_null() {
+        return column.nullable.then_some(None);
+    }
+    let value = match column.kind {
+        ColumnKind::Text => SqlValue::Text(value.as_str()?.to_string()),
+        ColumnKind::Int => SqlValue::Int(value.as_i64()?.try_into().ok()?),
+        ColumnKind::BigInt => SqlValue::BigInt(value.as_i64()?),
+        ColumnKind::Bool => SqlValue::Bool(value.as_bool()?),
+        ColumnKind::Timestamp => SqlValue::Timestamp(parse_timestamp(value.as_str()?).ok()?),
+    };
+    Some(Some(value))
+}
+
+/// Pushes `AND (...)` selecting rows strictly after `boundary` in `keys` order.
+///
+/// Postgres sorts NULLs last ascending and first descending, so a NULL
+/// boundary or nullable column needs explicit `IS NULL` arms.
+pub fn push_keyset(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    keys: &[SortKey],
+    boundary: &[Option<SqlValue>],
+) {
+    builder.push(" AND (FALSE");
+    for i in 0..keys.len() {
+        builder.push(" OR (TRUE");
+        for (key, value) in keys[..i].iter().zip(boundary) {
+            builder.push(" AND ").push(key.column.name);
+            match value {
+                Some(value) => {
+                    builder.push(" = ");
+                    value.push_bind(builder);
+                }
+                None => {
+                    builder.push(" IS NULL");
+                }
+            }
+        }
+
+        let key = keys[i];
+        builder.push(" AND ");
+        match (&boundary[i], key.descending) {
+            (Some(value), false) => {
+                builder.push("(").push(key.column.name).push(" > ");
+                value.push_bind(builder);
+                if key.column.nullable {
+                    builder.push(" OR ").push(key.column.name).push(" IS NULL");
+                }
+                builder.push(")");
+            }
+            (Some(value), true) => {
+                builder.push(key.column.name).push(" < ");
+                value.push_bind(builder);
+            }
+            (None, false) => {
+                builder.push("FALSE");
+            }
+            (None, true) => {
+                builder.push(key.column.name).push(" IS NOT NULL");
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(")");
+}
+
+/// How `q` is matched against a model's search columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Any search column contains `q`, case-insensitively.
+    #[default]
+    Contains,
+    /// Every word of `q` prefixes a word of the row; results are ranked.
+    FullText,
+}
+
+/// Marks a highlighted fragment in `ts_headline` output; swapped for
+/// `<mark>` tags once the rest of the text has been escaped.
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_STOP: char = '\u{3}';
+
+/// A free-text search over a model's search columns.
+#[derive(Debug, Clone)]
+pub struct TextSearch {
+    mode: SearchMode,
+    columns: &'static [&'static str],
+    vector: Option<&'static str>,
+    /// The `ILIKE` pattern, or the `to_tsquery` input.
+    term: String,
+}
+
+impl TextSearch {
+    /// `None` when there is nothing to search for. `vector` is the model's
+    /// stored `tsvector` column; without one it is computed per row.
+    pub fn new(
+        mode: SearchMode,
+        q: Option<&str>,
+        columns: &'static [&'static str],
+        vector: Option<&'static str>,
+    ) -> Option<Self> {
+        let q = q.map(str::trim).filter(|q| !q.is_empty())?;
+        if columns.is_empty() {
+            return None;
+        }
+
+        let term = match mode {
+            SearchMode::Contains => format!("%{}%", q),
+            SearchMode::FullText => {
+                // Only letters and digits reach to_tsquery, so its syntax can't be injected
+                let words: Vec<String> = q
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(|word| format!("{}:*", word.to_lowercase()))
+                    .collect();
+                if words.is_empty() {
+                    return None;
+                }
+                words.join(" & ")
+            }
+        };
+
+        Some(TextSearch {
+            mode,
+            columns,
+            vector,
+            term,
+        })
+    }
+
+    pub fn is_ranked(&self) -> bool {
+        self.mode == SearchMode::FullText
+    }
+
+    fn push_vector(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self.vector {
+            Some(vector) => {
+                builder.push(vector);
+            }
+            None => {
+                let text = self
+                    .columns
+                    .iter()
+                    .map(|column| format!("coalesce({}::text, '')", column))
+                    .collect::<Vec<_>>()
+                    .join(" || ' ' || ");
+                builder.push(format!("to_tsvector('simple', {})", text));
+            }
+        }
+    }
+
+    fn push_tsquery(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder
+            .push("to_tsquery('simple', ")
+            .push_bind(self.term.clone())
+            .push(")");
+    }
+
+    fn push_predicate(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self.mode {
+            SearchMode::Contains => {
+                builder.push(" AND (");
+                for (i, column) in self.columns.iter().enumerate() {
+                    if i > 0 {
+                        builder.push(" OR ");
+                    }
+                    builder
+                        .push(column)
+                        .push(" ILIKE ")
+                        .push_bind(self.term.clone());
+                }
+                builder.push(")");
+            }
+            SearchMode::FullText => {
+                builder.push(" AND ");
+                self.push_vector(builder);
+                builder.push(" @@ ");
+                self.push_tsquery(builder);
+            }
+        }
+    }
+
+    /// Pushes the extra select list of a ranked search: `search_rank` and
+    /// one `headline_<column>` per search column.
+    pub fn push_select(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push(", ts_rank(");
+        self.push_vector(builder);
+        builder.push(", ");
+        self.push_tsquery(builder);
+        builder.push(") AS search_rank");
+
+        let options = format!(
+            "StartSel={}, StopSel={}, HighlightAll=true",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        for column in self.columns {
+            builder.push(format!(", ts_headline('simple', {}::text, ", column));
+            self.push_tsquery(builder);
+            builder
+                .push(", ")
+                .push_bind(options.clone())
+                .push(format!(") AS headline_{}", column));
+        }
+    }
+
+    /// The highlighted search columns of a row read with
+    /// [`TextSearch::push_select`]. Columns without a match are left out.
+    pub fn highlights(&self, row: &PgRow) -> Result<HashMap<String, String>, sqlx::Error> {
+        let mut highlights = HashMap::new();
+        for column in self.columns {
+            let headline: Option<String> = row.try_get(format!("headline_{}", column).as_str())?;
+            if let Some(headline) = headline.filter(|text| text.contains(HIGHLIGHT_START)) {
+                highlights.insert(column.to_string(), mark_highlights(&headline));
+            }
+        }
+        Ok(highlights)
+    }
+}
+
+/// Escapes a headline for HTML and wraps its matches in `<mark>` tags.
+fn mark_highlights(headline: &str) -> String {
+    let mut marked = String::with_capacity(headline.len() + 16);
+    for c in headline.chars() {
+        match c {
+            HIGHLIGHT_START => marked.push_str("<mark>"),
+            HIGHLIGHT_STOP => marked.push_str("</mark>"),
+            '&' => marked.push_str("&amp;"),
+            '<' => marked.push_str("&lt;"),
+            '>' => marked.push_str("&gt;"),
+            '"' => marked.push_str("&quot;"),
+            '\'' => marked.push_str("&#39;"),
+            c => marked.push(c),
+        }
+    }
+    marked
+}
+
+/// Pushes `WHERE` with the free-text search and every condition, AND-ed.
+pub fn push_where(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    search: Option<&TextSearch>,
+    conditions: &[Condition],
+) {
+    builder.push(" WHERE TRUE");
+
+    if let Some(search) = search {
+        search.push_predicate(builder);
+    }
+
+    for condition in conditions {
+        builder.push(" AND ");
+        condition.push(builder);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: &[Column] = &[
+        Column::int("id").indexed(),
+        Column::text("name").indexed(),
+        Column::bool("active").filterable(),
+        Column::timestamp("archived_at").nullable().indexed(),
+        Column::text("notes"),
+    ];
+
+    fn filter(column: &str, op: Option<&str>, value: &str) -> RawFilter {
+        RawFilter {
+            column: column.to_string(),
+            op: op.map(str::to_string),
+            value: value.to_string(),
+        }
+    }
+
+    fn signature(sort: &str) -> String {
+        sort_signature(&parse_sort(COLUMNS, sort).unwrap())
+    }
+
+    #[test]
+    fn parse_filters_types_values_by_column() {
+        let conditions = parse_filters(
+            COLUMNS,
+            &[
+                filter("name", None, "Acme"),
+                filter("id", Some("in"), "1, 2,3"),
+                filter("active", Some("neq"), "yes"),
+                filter("archived_at", Some("null"), "false"),
+            ],
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &conditions[0],
+            Condition::Compare { column: "name", op: FilterOp::Eq, value }
+                if *value == SqlValue::Text("Acme".to_string())
+        ));
+        assert!(matches!(
+            &conditions[1],
+            Condition::Compare { column: "id", op: FilterOp::In, value }
+                if *value == SqlValue::IntList(vec![1, 2, 3])
+        ));
+        assert!(matches!(
+            &conditions[2],
+            Condition::Compare { column: "active", op: FilterOp::Neq, value }
+                if *value == SqlValue::Bool(true)
+        ));
+        assert!(matches!(
+            conditions[3],
+            Condition::Null {
+                column: "archived_at",
+                is_null: false
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_filters_reports_errors_by_parameter() {
+        let errors = parse_filters(
+            COLUMNS,
+            &[
+                filter("notes", None, "x"),
+                filter("name", Some("like"), "x"),
+                filter("id", Some("gt"), "abc"),
+                filter("active", Some("gt"), "true"),
+                filter("name", Some("null"), "true"),
+            ],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors["filter[notes]"],
+            ["Cannot filter by unknown column `notes`."]
+        );
+        assert!(errors["filter[name][like]"][0].starts_with("Unknown operator `like`."));
+        assert_eq!(errors["filter[id][gt]"], ["Expected an integer."]);
+        assert_eq!(
+            errors["filter[active][gt]"],
+            ["Booleans can only be compared with eq or neq."]
+        );
+        assert_eq!(
+            errors["filter[name][null]"],
+            ["Column `name` is never null."]
+        );
+    }
+
+    #[test]
+    fn parse_sort_appends_id_as_tie_breaker() {
+        assert_eq!(signature("-name"), "-name,id");
+        assert_eq!(signature(" +name , -archived_at "), "name,-archived_at,id");
+        assert_eq!(signature(""), "id");
+    }
+
+    #[test]
+    fn parse_sort_keeps_an_explicit_id() {
+        assert_eq!(signature("-id,name"), "-id,name");
+    }
+
+    #[test]
+    fn parse_sort_rejects_unknown_and_repeated_columns() {
+        let errors = parse_sort(COLUMNS, "notes,name,-name,missing").unwrap_err();
+        assert_eq!(
+            errors["sort"],
+            [
+                "Cannot sort by unknown column `notes`.",
+                "Column `name` is sorted on more than once.",
+                "Cannot sort by unknown column `missing`.",
+            ]
+        );
+    }
+
+    fn keyset_sql(sort: &str, boundary: &[Option<SqlValue>]) -> String {
+        let keys = parse_sort(COLUMNS, sort).unwrap();
+        let mut builder = QueryBuilder::new("");
+        push_keyset(&mut builder, &keys, boundary);
+        builder.sql().to_string()
+    }
+
+    #[test]
+    fn cursor_round_trips_through_its_encoding() {
+        let keys = parse_sort(COLUMNS, "-archived_at").unwrap();
+        let row = serde_json::json!({ "id": 7, "name": "Acme", "archived_at": null });
+        let cursor = Cursor::from_row(&keys, CursorDirection::Prev, &row);
+
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded.sort, "-archived_at,id");
+        assert_eq!(decoded.direction, CursorDirection::Prev);
+        assert_eq!(
+            decoded.boundary(&keys).unwrap(),
+            [None, Some(SqlValue::Int(7))]
+        );
+    }
+
+    #[test]
+    fn cursor_decode_rejects_garbage() {
+        assert!(Cursor::decode("not a cursor!").is_none());
+        assert!(Cursor::decode(&general_purpose::URL_SAFE_NO_PAD.encode("[1]")).is_none());
+    }
+
+    #[test]
+    fn cursor_boundary_requires_the_sort_it_was_issued_for() {
+        let keys = parse_sort(COLUMNS, "name").unwrap();
+        let row = serde_json::json!({ "id": 7, "name": "Acme" });
+        let cursor = Cursor::from_row(&keys, CursorDirection::Next, &row);
+
+        let other = parse_sort(COLUMNS, "-name").unwrap();
+        assert_eq!(
+            cursor.boundary(&other).unwrap_err(),
+            "The cursor does not match the requested sort."
+        );
+    }
+
+    #[test]
+    fn cursor_boundary_rejects_mistyped_values() {
+        let keys = parse_sort(COLUMNS, "name").unwrap();
+        let cursor = Cursor {
+            sort: sort_signature(&keys),
+            direction: CursorDirection::Next,
+            values: vec![serde_json::json!(1), serde_json::json!(7)],
+        };
+        assert_eq!(cursor.boundary(&keys).unwrap_err(), "Invalid cursor.");
+
+        // `id` is not nullable, so a NULL boundary cannot have come from a row
+        let cursor = Cursor {
+            values: vec![serde_json::json!("Acme"), serde_json::Value::Null],
+            ..cursor
+        };
+        assert_eq!(cursor.boundary(&keys).unwrap_err(), "Invalid cursor.");
+    }
+
+    #[test]
+    fn push_keyset_lets_nulls_follow_an_ascending_value() {
+        let boundary = [
+            Some(SqlValue::Timestamp(DateTime::UNIX_EPOCH)),
+            Some(SqlValue::Int(7)),
+        ];
+        assert_eq!(
+            keyset_sql("archived_at", &boundary),
+            " AND (FALSE \
+             OR (TRUE AND (archived_at > $1 OR archived_at IS NULL)) \
+             OR (TRUE AND archived_at = $2 AND (id > $3)))"
+        );
+    }
+
+    #[test]
+    fn push_keyset_after_an_ascending_null_stays_among_nulls() {
+        assert_eq!(
+            keyset_sql("archived_at", &[None, Some(SqlValue::Int(7))]),
+            " AND (FALSE \
+             OR (TRUE AND FALSE) \
+             OR (TRUE AND archived_at IS NULL AND (id > $1)))"
+        );
+    }
+
+    #[test]
+    fn push_keyset_after_a_descending_null_moves_on_to_values() {
+        assert_eq!(
+            keyset_sql("-archived_at", &[None, Some(SqlValue::Int(7))]),
+            " AND (FALSE \
+             OR (TRUE AND archived_at IS NOT NULL) \
+             OR (TRUE AND archived_at IS NULL AND (id > $1)))"
+        );
+    }
+
+    #[test]
+    fn mark_highlights_escapes_text_around_the_marks() {
+        let headline = format!(
+            "{}<b>Tom</b>{} & \"Jerry's\"",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        assert_eq!(
+            mark_highlights(&headline),
+            "<mark>&lt;b&gt;Tom&lt;/b&gt;</mark> &amp; &quot;Jerry&#39;s&quot;"
+        );
+    }
+
+    #[test]
+    fn fulltext_search_keeps_only_words() {
+        let search = TextSearch::new(
+            SearchMode::FullText,
+            Some(" Acme & (Pty) | !ltd:* "),
+            &["name"],
+            None,
+        )
+        .unwrap();
+        assert_eq!(search.term, "acme:* & pty:* & ltd:*");
+
+        assert!(TextSearch::new(SearchMode::FullText, Some("&|!"), &["name"], None).is_none());
+        assert!(TextSearch::new(SearchMode::Contains, Some("  "), &["name"], None).is_none());
+    }
+}
@@ -1,21 +1,47 @@
+use std::collections::HashMap;
+
 use axum::async_trait;
-use sqlx::{postgres::PgRow, prelude::FromRow, PgPool};
+use serde::Serialize;
+use sqlx::query_builder::Separated;
+use sqlx::{postgres::PgRow, prelude::FromRow, Acquire, PgConnection, Postgres, QueryBuilder, Row};
 
 use crate::error::{AppError, AppResult};
 
+use super::audit_log::{self, AuditAction};
+use super::etag::{ETag, ETagCondition};
+use super::fields::FieldSet;
+use super::patch::Changeset;
+use super::query::{
+    self, Column, Condition, Cursor, CursorDirection, SortKey, SqlValue, TextSearch,
+};
 use super::{List, ListOptions, Paginator};
 
 pub type PgQuery<'q, T> = sqlx::query::QueryAs<'q, sqlx::Postgres, T, sqlx::postgres::PgArguments>;
 
 #[async_trait]
-pub trait ModelRepository: Sized + for<'r> FromRow<'r, PgRow> + Unpin {
+pub trait ModelRepository:
+    Sized + Send + Sync + Serialize + for<'r> FromRow<'r, PgRow> + Unpin
+{
     type CreateModel: Send + Sync;
-    type UpdateModel: Send + Sync;
+    type UpdateModel: Into<Changeset> + Send + Sync;
 
     const TABLE_NAME: &'static str;
     const CREATE_FIELDS: &'static [&'static str];
+    /// Columns an update's changeset may write.
     const UPDATE_FIELDS: &'static [&'static str];
     const SEARCH_COLUMNS: &'static [&'static str];
+    /// Every field the API shows, in declaration order.
+    const FIELDS: &'static [&'static str];
+    /// Columns that list queries may sort or filter on.
+    const COLUMNS: &'static [Column];
+    /// Timestamp column that marks a row as archived. Models that set this
+    /// are soft-deleted: `delete` archives and `purge` removes the row.
+    const ARCHIVE_COLUMN: Option<&'static str> = None;
+    /// Stored `tsvector` over `SEARCH_COLUMNS`, used by `search=fulltext`.
+    /// Without one, the vector is computed per row.
+    const SEARCH_VECTOR: Option<&'static str> = None;
+    /// Whether writes are recorded in the audit log, in the same transaction.
+    const AUDITED: bool = true;
 
     fn create_placeholders() -> String {
         (1..=Self::CREATE_FIELDS.len())
@@ -24,7 +50,11 @@ pub trait ModelRepository: Sized + for<'r> FromRow<'r, PgRow> + Unpin {
             .join(", ")
     }
 
-    async fn create(pool: &PgPool, data: Self::CreateModel) -> AppResult<Self> {
+    async fn create<'c, A>(conn: A, data: Self::CreateModel) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = conn.begin().await?;
         let query = format!(
             "INSERT INTO {} ({}) VALUES ({}) RETURNING *",
             Self::TABLE_NAME,
@@ -36,107 +66,624 @@ pub trait ModelRepository: Sized + for<'r> FromRow<'r, PgRow> + Unpin {
 
         let query = Self::bind_create(query, data);
 
-        query.fetch_one(pool).await.map_err(AppError::from)
+        let created = query.fetch_one(&mut *tx).await?;
+        audit_log::record(&mut tx, AuditAction::Create, None, Some(&created)).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn get<'c, A>(conn: A, id: i32) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        Self::get_sparse(conn, id, None).await
     }
 
-    async fn get(pool: &PgPool, id: i32) -> AppResult<Self> {
-        let query = format!("SELECT * FROM {} WHERE id = $1", Self::TABLE_NAME);
+    /// Like [`ModelRepository::get`], selecting only the columns of `fields`
+    /// when given.
+    async fn get_sparse<'c, A>(conn: A, id: i32, fields: Option<&FieldSet>) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = conn.acquire().await?;
+        let query = format!(
+            "SELECT {} FROM {} WHERE id = $1",
+            fields.map_or("*".to_string(), |fields| fields.select_list(&[])),
+            Self::TABLE_NAME
+        );
         sqlx::query_as::<_, Self>(&query)
             .bind(id)
-            .fetch_one(pool)
+            .fetch_one(&mut *conn)
             .await
             .map_err(AppError::from)
     }
 
-    async fn update(pool: &PgPool, id: i32, data: Self::UpdateModel) -> AppResult<Self> {
-        let update_fields = Self::UPDATE_FIELDS
-            .iter()
-            .enumerate()
-            .map(|(i, field)| format!("{} = ${}", field, i + 1))
-            .collect::<Vec<_>>()
-            .join(", ");
+    /// Writes only the columns present in the changeset; an empty changeset
+    /// leaves the row untouched and returns it as is.
+    async fn update<'c, A>(conn: A, id: i32, data: Self::UpdateModel) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        Self::update_if_match(conn, id, data, None).await
+    }
 
-        let query = format!(
-            "UPDATE {} SET {} WHERE id = $1 RETURNING *",
-            Self::TABLE_NAME,
-            update_fields
-        );
+    /// Like [`ModelRepository::update`], but fails with
+    /// [`AppError::PreconditionFailed`] unless the locked row still matches
+    /// `if_match`.
+    async fn update_if_match<'c, A>(
+        conn: A,
+        id: i32,
+        data: Self::UpdateModel,
+        if_match: Option<&ETagCondition>,
+    ) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let changes: Changeset = data.into();
+        debug_assert!(changes
+            .columns()
+            .all(|column| Self::UPDATE_FIELDS.contains(&column)));
 
-        let query = sqlx::query_as::<_, Self>(&query);
-        let query = Self::bind_update(query, id, data);
+        if changes.is_empty() && if_match.is_none() {
+            return Self::get(conn, id).await;
+        }
 
-        query.fetch_one(pool).await.map_err(AppError::from)
+        let mut tx = conn.begin().await?;
+        let before = fetch_locked::<Self>(&mut tx, id).await?;
+        if let Some(condition) = if_match {
+            if !condition.matches_strong(before.etag().as_ref()) {
+                return Err(AppError::PreconditionFailed);
+            }
+        }
+        if changes.is_empty() {
+            return Ok(before);
+        }
+
+        let mut query = QueryBuilder::new(format!("UPDATE {} SET ", Self::TABLE_NAME));
+        changes.push_set(&mut query);
+        query
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(" RETURNING *");
+
+        let updated = query.build_query_as::<Self>().fetch_one(&mut *tx).await?;
+        audit_log::record(&mut tx, AuditAction::Update, Some(&before), Some(&updated)).await?;
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn list<'c, A>(conn: A, options: &ListOptions) -> AppResult<List<Self>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        Self::list_scoped(conn, None, options).await
+    }
+
+    /// Lists rows whose `column` equals `value`, e.g. the contacts of one customer.
+    async fn list_by<'c, A>(
+        conn: A,
+        column: &'static str,
+        value: i32,
+        options: &ListOptions,
+    ) -> AppResult<List<Self>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        Self::list_scoped(conn, Some((column, value)), options).await
     }
 
-    async fn list(pool: &PgPool, options: &ListOptions) -> AppResult<List<Self>> {
-        let search_query = format!("%{}%", options.q.clone().unwrap_or_default());
+    async fn list_scoped<'c, A>(
+        conn: A,
+        scope: Option<(&'static str, i32)>,
+        options: &ListOptions,
+    ) -> AppResult<List<Self>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = conn.acquire().await?;
         let page = options.page.unwrap_or(1);
-        let per_page = options.per_page.unwrap_or(10).clamp(10, 1000);
-        let offset = (page as i64 - 1) * per_page as i64;
+        let per_page = options.page_size();
+        let offset = match page.checked_sub(1) {
+            Some(skipped) => skipped
+                .checked_mul(per_page as u64)
+                .and_then(|offset| i64::try_from(offset).ok())
+                .ok_or("The page is too large."),
+            None => Err("The page must be at least 1."),
+        };
+        let offset = match offset {
+            Ok(offset) => offset,
+            Err(message) => {
+                return Err(AppError::ValidationError(HashMap::from([(
+                    "page".to_string(),
+                    vec![message.to_string()],
+                )])))
+            }
+        };
 
-        let where_clause: String = Self::SEARCH_COLUMNS
-            .iter()
-            .map(|col| format!("{} ILIKE $1", col))
-            .collect::<Vec<_>>()
-            .join(" OR ");
+        let sort = query::parse_sort(Self::COLUMNS, &options.sort_spec());
+        let filters = query::parse_filters(Self::COLUMNS, &options.filters);
+        let fields = FieldSet::parse(options.fields.as_deref(), Self::FIELDS);
+        let (sort, mut conditions, fields) = match (sort, filters, fields) {
+            (Ok(sort), Ok(conditions), Ok(fields)) => (sort, conditions, fields),
+            (sort, filters, fields) => {
+                let mut errors = sort.err().unwrap_or_default();
+                errors.extend(filters.err().unwrap_or_default());
+                if let Err(AppError::ValidationError(field_errors)) = fields {
+                    errors.extend(field_errors);
+                }
+                return Err(AppError::ValidationError(errors));
+            }
+        };
+        // Cursors are built from the sort keys of the edge rows
+        let sort_columns: Vec<&str> = sort.iter().map(|key| key.column.name).collect();
+        let select_list = fields
+            .as_ref()
+            .map_or("*".to_string(), |fields| fields.select_list(&sort_columns));
 
-        let sort_by = options.sort_by.as_deref().unwrap_or("id");
-        let sort_order = if options.ascending.unwrap_or(true) {
-            "ASC"
+        if let Some((column, value)) = scope {
+            conditions.push(Condition::eq(column, SqlValue::Int(value)));
+        }
+
+        if let (Some(column), Some(is_null)) = (Self::ARCHIVE_COLUMN, options.archived_is_null()) {
+            conditions.push(Condition::Null { column, is_null });
+        }
+
+        let search = TextSearch::new(
+            options.search_mode(),
+            options.q.as_deref(),
+            Self::SEARCH_COLUMNS,
+            Self::SEARCH_VECTOR,
+        );
+        let ranked = search.as_ref().is_some_and(TextSearch::is_ranked);
+        if ranked && options.uses_cursor() {
+            return Err(AppError::ValidationError(HashMap::from([(
+                "search".to_string(),
+                vec!["Relevance ranking cannot be combined with cursor pagination.".to_string()],
+            )])));
+        }
+
+        let total_count = if options.wants_count() {
+            let mut count_query =
+                QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", Self::TABLE_NAME));
+            query::push_where(&mut count_query, search.as_ref(), &conditions);
+            let total_count: i64 = count_query
+                .build_query_scalar()
+                .fetch_one(&mut *conn)
+                .await?;
+            Some(total_count as u64)
         } else {
-            "DESC"
+            None
         };
 
-        let total_count_query = format!(
-            "SELECT COUNT(*) FROM {} WHERE {}",
-            Self::TABLE_NAME,
-            where_clause
-        );
-        let total_count: (i64,) = sqlx::query_as(&total_count_query)
-            .bind(&search_query)
-            .fetch_one(pool)
+        if options.uses_cursor() {
+            let mut list = Self::list_keyset(
+                &mut conn,
+                options,
+                search.as_ref(),
+                &sort,
+                &conditions,
+                &select_list,
+                total_count,
+            )
             .await?;
+            list.fields = fields;
+            return Ok(list);
+        }
 
-        let query = format!(
-            "SELECT * FROM {} WHERE {} ORDER BY {} {} LIMIT $2 OFFSET $3",
-            Self::TABLE_NAME,
-            where_clause,
-            sort_by,
-            sort_order
-        );
+        let mut data_query = QueryBuilder::new(format!("SELECT {}", select_list));
+        if let Some(search) = search.as_ref().filter(|_| ranked) {
+            search.push_select(&mut data_query);
+        }
+        data_query.push(format!(" FROM {}", Self::TABLE_NAME));
+        query::push_where(&mut data_query, search.as_ref(), &conditions);
+        if ranked {
+            query::push_ranked_order_by(&mut data_query, &sort);
+        } else {
+            query::push_order_by(&mut data_query, &sort);
+        }
+        data_query
+            .push(" LIMIT ")
+            .push_bind(per_page as i64)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let (data, highlights) = match search.as_ref().filter(|_| ranked) {
+            Some(search) => {
+                let rows = data_query.build().fetch_all(&mut *conn).await?;
+                let mut data = Vec::with_capacity(rows.len());
+                let mut highlights = Vec::with_capacity(rows.len());
+                for row in rows {
+                    data.push(Self::from_row(&row)?);
+                    highlights.push(search.highlights(&row)?);
+                }
+                (data, Some(highlights))
+            }
+            None => {
+                let data = data_query
+                    .build_query_as::<Self>()
+                    .fetch_all(&mut *conn)
+                    .await?;
+                (data, None)
+            }
+        };
+
+        Ok(List {
+            data,
+            highlights,
+            pagination: Paginator {
+                current_page: Some(page),
+                per_page,
+                total_pages: total_count.map(|count| count.div_ceil(per_page as u64)),
+                total_count,
+                next_cursor: None,
+                prev_cursor: None,
+            },
+            fields,
+        })
+    }
+
+    /// Fetches one page after (or before) the row encoded in `options.cursor`.
+    ///
+    /// One extra row is read to tell whether another page follows.
+    async fn list_keyset(
+        conn: &mut PgConnection,
+        options: &ListOptions,
+        search: Option<&TextSearch>,
+        sort: &[SortKey],
+        conditions: &[Condition],
+        select_list: &str,
+        total_count: Option<u64>,
+    ) -> AppResult<List<Self>> {
+        let per_page = options.page_size();
+        let cursor = match options.cursor.as_deref() {
+            Some(raw) => {
+                let cursor = Cursor::decode(raw).ok_or_else(|| cursor_error("Invalid cursor."))?;
+                let boundary = cursor.boundary(sort).map_err(|err| cursor_error(&err))?;
+                Some((cursor.direction, boundary))
+            }
+            None => None,
+        };
+
+        let direction = cursor
+            .as_ref()
+            .map_or(CursorDirection::Next, |(direction, _)| *direction);
+        let keys: Vec<SortKey> = match direction {
+            CursorDirection::Next => sort.to_vec(),
+            CursorDirection::Prev => sort.iter().map(|key| key.reversed()).collect(),
+        };
+
+        let mut data_query =
+            QueryBuilder::new(format!("SELECT {} FROM {}", select_list, Self::TABLE_NAME));
+        query::push_where(&mut data_query, search, conditions);
+        if let Some((_, boundary)) = &cursor {
+            query::push_keyset(&mut data_query, &keys, boundary);
+        }
+        query::push_order_by(&mut data_query, &keys);
+        data_query.push(" LIMIT ").push_bind(per_page as i64 + 1);
 
-        let data = sqlx::query_as::<_, Self>(&query)
-            .bind(&search_query)
-            .bind(per_page as i64)
-            .bind(offset)
-            .fetch_all(pool)
+        let mut data = data_query
+            .build_query_as::<Self>()
+            .fetch_all(&mut *conn)
             .await?;
+        let has_more = data.len() > per_page as usize;
+        data.truncate(per_page as usize);
+
+        let (has_next, has_prev) = match direction {
+            CursorDirection::Next => (has_more, cursor.is_some()),
+            CursorDirection::Prev => {
+                data.reverse();
+                (true, has_more)
+            }
+        };
 
-        let total_pages = (total_count.0 as f64 / per_page as f64).ceil() as u16;
+        let edge_cursor = |row: Option<&Self>, direction| {
+            let row = serde_json::to_value(row?).ok()?;
+            Some(Cursor::from_row(sort, direction, &row).encode())
+        };
+        let next_cursor = has_next
+            .then(|| edge_cursor(data.last(), CursorDirection::Next))
+            .flatten();
+        let prev_cursor = has_prev
+            .then(|| edge_cursor(data.first(), CursorDirection::Prev))
+            .flatten();
 
         Ok(List {
             data,
+            highlights: None,
             pagination: Paginator {
-                current_page: page,
+                current_page: None,
                 per_page,
-                total_pages,
-                total_count: total_count.0 as u16,
+                total_pages: None,
+                total_count,
+                next_cursor,
+                prev_cursor,
             },
+            fields: None,
         })
     }
 
-    async fn delete(pool: &PgPool, id: i32) -> AppResult<()> {
-        let query = format!("DELETE FROM {} WHERE id = $1", Self::TABLE_NAME);
-        let _ = sqlx::query(&query)
+    /// Archives the row when the model is soft-deleted, otherwise removes it.
+    async fn delete<'c, A>(conn: A, id: i32) -> AppResult<()>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = conn.begin().await?;
+        match Self::ARCHIVE_COLUMN {
+            Some(column) => {
+                let before = fetch_locked::<Self>(&mut tx, id).await?;
+                let query = format!(
+                    "UPDATE {} SET {} = now() WHERE id = $1 AND {} IS NULL RETURNING *",
+                    Self::TABLE_NAME,
+                    column,
+                    column
+                );
+                let archived = sqlx::query_as::<_, Self>(&query)
+                    .bind(id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                audit_log::record(
+                    &mut tx,
+                    AuditAction::Archive,
+                    Some(&before),
+                    Some(&archived),
+                )
+                .await?;
+            }
+            None => {
+                let query = format!("DELETE FROM {} WHERE id = $1 RETURNING *", Self::TABLE_NAME);
+                let deleted = sqlx::query_as::<_, Self>(&query)
+                    .bind(id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                audit_log::record(&mut tx, AuditAction::Delete, Some(&deleted), None).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Un-archives a soft-deleted row. Rows that are not archived, and models
+    /// without an archive column, are reported as not found.
+    async fn restore<'c, A>(conn: A, id: i32) -> AppResult<Self>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let Some(column) = Self::ARCHIVE_COLUMN else {
+            return Err(AppError::DatabaseError(sqlx::Error::RowNotFound));
+        };
+
+        let mut tx = conn.begin().await?;
+        let before = fetch_locked::<Self>(&mut tx, id).await?;
+        let query = format!(
+            "UPDATE {} SET {} = NULL WHERE id = $1 AND {} IS NOT NULL RETURNING *",
+            Self::TABLE_NAME,
+            column,
+            column
+        );
+        let restored = sqlx::query_as::<_, Self>(&query)
             .bind(id)
-            .execute(pool)
-            .await
-            .map_err(AppError::from)?;
+            .fetch_one(&mut *tx)
+            .await?;
+        audit_log::record(
+            &mut tx,
+            AuditAction::Restore,
+            Some(&before),
+            Some(&restored),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(restored)
+    }
+
+    /// Permanently removes the row, archived or not.
+    async fn purge<'c, A>(conn: A, id: i32) -> AppResult<()>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = conn.begin().await?;
+        let query = format!("DELETE FROM {} WHERE id = $1 RETURNING *", Self::TABLE_NAME);
+        let purged = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        audit_log::record(&mut tx, AuditAction::Purge, Some(&purged), None).await?;
+        tx.commit().await?;
         Ok(())
     }
 
+    /// Inserts all rows with multi-row `INSERT` statements, in one transaction.
+    /// Rows are returned in input order.
+    async fn create_many<'c, A>(conn: A, rows: Vec<Self::CreateModel>) -> AppResult<Vec<Self>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        insert_many(conn, rows, None).await
+    }
+
+    /// Like [`ModelRepository::create_many`], but rows whose `conflict_key`
+    /// already exists get the `overwrite` fields updated instead. Create
+    /// fields not listed keep their stored values.
+    ///
+    /// A conflict key may appear only once per call; Postgres rejects a
+    /// statement that would update the same row twice. The audit log records
+    /// the new values of updated rows, but not the values they replaced.
+    async fn upsert<'c, A>(
+        conn: A,
+        rows: Vec<Self::CreateModel>,
+        conflict_key: &'static str,
+        overwrite: &[&'static str],
+    ) -> AppResult<Vec<Self>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        debug_assert!(Self::CREATE_FIELDS.contains(&conflict_key));
+        debug_assert!(overwrite
+            .iter()
+            .all(|field| Self::CREATE_FIELDS.contains(field)));
+        insert_many(conn, rows, Some((conflict_key, overwrite))).await
+    }
+
+    /// Deletes (or archives) every listed row, returning the ids affected.
+    /// Missing and already archived ids are skipped.
+    async fn delete_many<'c, A>(conn: A, ids: &[i32]) -> AppResult<Vec<i32>>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = conn.begin().await?;
+        let entries = match Self::ARCHIVE_COLUMN {
+            Some(column) => {
+                let query = format!(
+                    "SELECT * FROM {} WHERE id = ANY($1) AND {} IS NULL ORDER BY id FOR UPDATE",
+                    Self::TABLE_NAME,
+                    column
+                );
+                let before = sqlx::query_as::<_, Self>(&query)
+                    .bind(ids)
+                    .fetch_all(&mut *tx)
+                    .await?;
+                let query = format!(
+                    "WITH archived AS (UPDATE {} SET {} = now() \
+                     WHERE id = ANY($1) AND {} IS NULL RETURNING *) \
+                     SELECT * FROM archived ORDER BY id",
+                    Self::TABLE_NAME,
+                    column,
+                    column
+                );
+                let archived = sqlx::query_as::<_, Self>(&query)
+                    .bind(ids)
+                    .fetch_all(&mut *tx)
+                    .await?;
+                before
+                    .into_iter()
+                    .zip(archived)
+                    .map(|(before, after)| (AuditAction::Archive, Some(before), Some(after)))
+                    .collect::<Vec<_>>()
+            }
+            None => {
+                let query = format!(
+                    "DELETE FROM {} WHERE id = ANY($1) RETURNING *",
+                    Self::TABLE_NAME
+                );
+                sqlx::query_as::<_, Self>(&query)
+                    .bind(ids)
+                    .fetch_all(&mut *tx)
+                    .await?
+                    .into_iter()
+                    .map(|row| (AuditAction::Delete, Some(row), None))
+                    .collect()
+            }
+        };
+
+        let deleted = entries
+            .iter()
+            .filter_map(|(_, before, _)| before.as_ref().and_then(audit_log::row_id))
+            .collect();
+        audit_log::record_many(&mut tx, &entries).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// The row's current version, for `ETag` and `If-Match`. `None` for
+    /// models without a version column.
+    fn etag(&self) -> Option<ETag> {
+        None
+    }
+
     fn bind_create(query: PgQuery<'_, Self>, data: Self::CreateModel) -> PgQuery<'_, Self>;
 
-    fn bind_update(query: PgQuery<'_, Self>, id: i32, data: Self::UpdateModel)
-        -> PgQuery<'_, Self>;
+    /// Pushes one row of `CREATE_FIELDS` values for a multi-row insert.
+    fn push_create_values<'args>(
+        row: &mut Separated<'_, 'args, Postgres, &'static str>,
+        data: Self::CreateModel,
+    );
+}
+
+/// Postgres accepts at most this many bind parameters per statement.
+const MAX_BIND_PARAMS: usize = 65535;
+
+async fn insert_many<'c, M, A>(
+    conn: A,
+    rows: Vec<M::CreateModel>,
+    conflict: Option<(&'static str, &[&'static str])>,
+) -> AppResult<Vec<M>>
+where
+    M: ModelRepository,
+    A: Acquire<'c, Database = Postgres> + Send,
+{
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let chunk_size = MAX_BIND_PARAMS / M::CREATE_FIELDS.len().max(1);
+    let mut tx = conn.begin().await?;
+    let mut created = Vec::with_capacity(rows.len());
+    let mut rows = rows.into_iter();
+
+    loop {
+        let chunk: Vec<M::CreateModel> = rows.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+
+        let mut query = QueryBuilder::new(format!(
+            "INSERT INTO {} ({}) ",
+            M::TABLE_NAME,
+            M::CREATE_FIELDS.join(", ")
+        ));
+        query.push_values(chunk, |mut row, data| M::push_create_values(&mut row, data));
+
+        if let Some((conflict_key, overwrite)) = conflict {
+            let updates = overwrite
+                .iter()
+                .filter(|field| **field != conflict_key)
+                .map(|field| format!("{} = EXCLUDED.{}", field, field))
+                .collect::<Vec<_>>();
+            query.push(format!(" ON CONFLICT ({}) DO ", conflict_key));
+            if updates.is_empty() {
+                // DO NOTHING would not return the existing row
+                query.push(format!(
+                    "UPDATE SET {} = EXCLUDED.{}",
+                    conflict_key, conflict_key
+                ));
+            } else {
+                query.push(format!("UPDATE SET {}", updates.join(", ")));
+            }
+        }
+        // xmax is only set on rows that already existed and were updated
+        query.push(" RETURNING *, (xmax = 0) AS inserted");
+
+        let mut entries = Vec::new();
+        for row in query.build().fetch_all(&mut *tx).await? {
+            let action = match row.try_get("inserted")? {
+                true => AuditAction::Create,
+                false => AuditAction::Upsert,
+            };
+            entries.push((action, None, Some(M::from_row(&row)?)));
+        }
+        audit_log::record_many(&mut tx, &entries).await?;
+        created.extend(entries.into_iter().filter_map(|(_, _, row)| row));
+    }
+
+    tx.commit().await?;
+    Ok(created)
+}
+
+/// Fetches a row and locks it until the end of the transaction.
+async fn fetch_locked<M>(conn: &mut PgConnection, id: i32) -> AppResult<M>
+where
+    M: ModelRepository,
+{
+    let query = format!("SELECT * FROM {} WHERE id = $1 FOR UPDATE", M::TABLE_NAME);
+    sqlx::query_as::<_, M>(&query)
+        .bind(id)
+        .fetch_one(conn)
+        .await
+        .map_err(AppError::from)
+}
+
+fn cursor_error(message: &str) -> AppError {
+    AppError::ValidationError(HashMap::from([(
+        "cursor".to_string(),
+        vec![message.to_string()],
+    )]))
 }
@@ -1,40 +1,272 @@
+use asg_macros::ModelRepository;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use sqlx::prelude::FromRow;
+use serde_json::{json, Value};
+use sqlx::{prelude::FromRow, PgConnection, PgPool};
 
-use super::repository::{ModelRepository, PgQuery};
+use crate::error::AppResult;
 
-#[derive(Serialize, Debug, FromRow)]
+use super::audit_log::{self, AuditAction};
+use super::etag::ETagCondition;
+use super::include::{self, Related, Relation};
+use super::patch::Patch;
+use super::permission_catalogue::{PermissionName, ADMIN_ROLE};
+use super::permissions::Permission;
+use super::repository::ModelRepository;
+
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(table = "roles", create = RoleForCreate, update = RoleForUpdate)]
 pub struct Role {
+    #[model(indexed)]
     pub id: i32,
+    #[model(create, update, search, indexed)]
     pub name: String,
+    #[model(indexed)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[model(indexed, version)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 pub struct RoleForCreate {
     pub name: String,
 }
 
-impl ModelRepository for Role {
-    type CreateModel = RoleForCreate;
+#[derive(Default)]
+pub struct RoleForUpdate {
+    pub name: Patch<String>,
+}
+
+impl Role {
+    pub async fn find_by_name(pool: &PgPool, name: &str) -> AppResult<Self> {
+        let role = sqlx::query_as::<_, Self>("SELECT * FROM roles WHERE name = $1")
+            .bind(name)
+            .fetch_one(pool)
+            .await?;
+        Ok(role)
+    }
+
+    pub async fn assign_to_user(pool: &PgPool, role_id: i32, user_id: i32) -> AppResult<()> {
+        let mut tx = pool.begin().await?;
+
+        let inserted = sqlx::query(
+            "INSERT INTO user_has_roles (user_id, role_id) VALUES ($1, $2) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(role_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            record_user_role(&mut tx, AuditAction::Create, role_id, user_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the role from the user unless that would leave no admin,
+    /// returning whether it was removed.
+    pub async fn remove_from_user_unless_last_admin(
+        pool: &PgPool,
+        role_id: i32,
+        user_id: i32,
+    ) -> AppResult<bool> {
+        let mut tx = pool.begin().await?;
 
-    type UpdateModel = RoleForCreate;
+        if lock_is_admin_role(&mut tx, role_id).await? == Some(true) {
+            let admins = lock_admin_ids(&mut tx).await?;
+            if admins.contains(&user_id) && !admins.iter().any(|&admin| admin != user_id) {
+                return Ok(false);
+            }
+        }
 
-    const TABLE_NAME: &'static str = "roles";
+        let deleted = sqlx::query("DELETE FROM user_has_roles WHERE user_id = $1 AND role_id = $2")
+            .bind(user_id)
+            .bind(role_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if deleted > 0 {
+            record_user_role(&mut tx, AuditAction::Delete, role_id, user_id).await?;
+        }
 
-    const CREATE_FIELDS: &'static [&'static str] = &["name"];
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn for_user(pool: &PgPool, user_id: i32) -> AppResult<Vec<Self>> {
+        let roles = sqlx::query_as::<_, Self>(
+            "SELECT r.* FROM roles r \
+             JOIN user_has_roles ur ON ur.role_id = r.id \
+             WHERE ur.user_id = $1 \
+             ORDER BY r.name",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(roles)
+    }
 
-    const UPDATE_FIELDS: &'static [&'static str] = &["name"];
+    pub async fn grant_permission(
+        pool: &PgPool,
+        role_id: i32,
+        permission_id: i32,
+    ) -> AppResult<()> {
+        let mut tx = pool.begin().await?;
 
-    const SEARCH_COLUMNS: &'static [&'static str] = &["name"];
+        let inserted = sqlx::query(
+            "INSERT INTO role_has_permissions (role_id, permission_id) VALUES ($1, $2) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(role_id)
+        .bind(permission_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            record_role_permission(&mut tx, AuditAction::Create, role_id, permission_id).await?;
+        }
 
-    fn bind_create(query: PgQuery<'_, Self>, data: Self::CreateModel) -> PgQuery<'_, Self> {
-        query.bind(data.name)
+        tx.commit().await?;
+        Ok(())
     }
 
-    fn bind_update(
-        query: PgQuery<'_, Self>,
+    /// Revokes the permission from the role unless it is the admin role,
+    /// which always holds every permission. Returns whether it was revoked.
+    pub async fn revoke_permission_unless_admin(
+        pool: &PgPool,
+        role_id: i32,
+        permission_id: i32,
+    ) -> AppResult<bool> {
+        let mut tx = pool.begin().await?;
+
+        if lock_is_admin_role(&mut tx, role_id).await? == Some(true) {
+            return Ok(false);
+        }
+
+        let deleted = sqlx::query(
+            "DELETE FROM role_has_permissions WHERE role_id = $1 AND permission_id = $2",
+        )
+        .bind(role_id)
+        .bind(permission_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if deleted > 0 {
+            record_role_permission(&mut tx, AuditAction::Delete, role_id, permission_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Deletes the role unless it is the admin role, returning whether it
+    /// was deleted.
+    pub async fn delete_unless_admin(pool: &PgPool, id: i32) -> AppResult<bool> {
+        let mut tx = pool.begin().await?;
+
+        if lock_is_admin_role(&mut tx, id).await? == Some(true) {
+            return Ok(false);
+        }
+        Self::delete(&mut *tx, id).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Updates the role unless that would rename the admin role; `None` when
+    /// it would.
+    pub async fn update_unless_admin(
+        pool: &PgPool,
         id: i32,
-        data: Self::UpdateModel,
-    ) -> PgQuery<'_, Self> {
-        query.bind(id).bind(data.name)
+        data: RoleForUpdate,
+        if_match: Option<&ETagCondition>,
+    ) -> AppResult<Option<Self>> {
+        let mut tx = pool.begin().await?;
+
+        let renamed = data.name.value().is_some_and(|name| name != ADMIN_ROLE);
+        if renamed && lock_is_admin_role(&mut tx, id).await? == Some(true) {
+            return Ok(None);
+        }
+        let role = Self::update_if_match(&mut *tx, id, data, if_match).await?;
+
+        tx.commit().await?;
+        Ok(Some(role))
+    }
+}
+
+async fn record_user_role(
+    conn: &mut PgConnection,
+    action: AuditAction,
+    role_id: i32,
+    user_id: i32,
+) -> AppResult<()> {
+    let link = json!({ "user_id": user_id, "role_id": role_id });
+    audit_log::record_link(conn, "user_has_roles", action, user_id, link).await
+}
+
+async fn record_role_permission(
+    conn: &mut PgConnection,
+    action: AuditAction,
+    role_id: i32,
+    permission_id: i32,
+) -> AppResult<()> {
+    let link = json!({ "role_id": role_id, "permission_id": permission_id });
+    audit_log::record_link(conn, "role_has_permissions", action, role_id, link).await
+}
+
+/// Whether role `id` is the admin role, locking it for the transaction so
+/// it cannot be renamed meanwhile; `None` when there is no such role.
+async fn lock_is_admin_role(conn: &mut PgConnection, id: i32) -> AppResult<Option<bool>> {
+    Ok(
+        sqlx::query_scalar("SELECT name = $2 FROM roles WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .bind(ADMIN_ROLE)
+            .fetch_optional(conn)
+            .await?,
+    )
+}
+
+/// The ids of the users holding the admin role.
+///
+/// Admin memberships are locked for the transaction, so two concurrent
+/// changes cannot each remove "the other" admin.
+pub(crate) async fn lock_admin_ids(conn: &mut PgConnection) -> AppResult<Vec<i32>> {
+    Ok(sqlx::query_scalar(
+        "SELECT ur.user_id FROM user_has_roles ur \
+         JOIN roles r ON r.id = ur.role_id \
+         WHERE r.name = $1 \
+         FOR UPDATE OF ur",
+    )
+    .bind(ADMIN_ROLE)
+    .fetch_all(conn)
+    .await?)
+}
+
+#[async_trait]
+impl Related for Role {
+    const RELATIONS: &'static [Relation] = &[Relation {
+        name: "permissions",
+        permission: PermissionName::RolesView,
+    }];
+
+    async fn load_relation(pool: &PgPool, rows: &[Self], relation: &str) -> AppResult<Vec<Value>> {
+        match relation {
+            "permissions" => {
+                include::has_many::<Permission>(
+                    pool,
+                    "SELECT p.*, rp.role_id AS owner_id FROM permissions p \
+                     JOIN role_has_permissions rp ON rp.permission_id = p.id \
+                     WHERE rp.role_id = ANY($1) \
+                     ORDER BY p.name",
+                    rows.iter().map(|role| role.id).collect(),
+                )
+                .await
+            }
+            _ => Err(include::unknown_relation(relation)),
+        }
     }
 }
@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+
+use crate::{
+    app_state::config::TokenLifetimes,
+    auth::tokens::{expires_at, hash_token, random_token},
+    db,
+    error::{AppError, AppResult},
+};
+
+/// A login. Its refresh token is rotated on every refresh, and its access
+/// tokens are revoked when it ends.
+#[derive(Debug, FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub access_jti: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// The tokens handed out when a session starts or is refreshed. The refresh
+/// token is only ever returned here; the database keeps its hash.
+#[derive(Debug)]
+pub struct IssuedSession {
+    pub session_id: i32,
+    pub user_id: i32,
+    pub access_jti: String,
+    pub refresh_token: String,
+}
+
+impl Session {
+    /// Starts a session for `user_id`. Revoked tokens that have expired
+    /// anyway are cleared out at the same time.
+    pub async fn start(
+        pool: &PgPool,
+        user_id: i32,
+        lifetimes: TokenLifetimes,
+    ) -> AppResult<IssuedSession> {
+        db::transaction(pool, |tx| {
+            Box::pin(async move {
+                let access_jti = random_token();
+                let session_id: i32 = sqlx::query_scalar(
+                    "INSERT INTO sessions (user_id, access_jti, expires_at) \
+                     VALUES ($1, $2, $3) RETURNING id",
+                )
+                .bind(user_id)
+                .bind(&access_jti)
+                .bind(expires_at(lifetimes.refresh))
+                .fetch_one(&mut **tx)
+                .await?;
+
+                let refresh_token = insert_refresh_token(tx, session_id).await?;
+                purge_revoked(&mut **tx).await?;
+                Ok(IssuedSession {
+                    session_id,
+                    user_id,
+                    access_jti,
+                    refresh_token,
+                })
+            })
+        })
+        .await
+    }
+
+    /// Exchanges `refresh_token` for a new one and a new access token id,
+    /// extending the session.
+    ///
+    /// A token that was already exchanged is taken to have leaked: the
+    /// session is revoked, so neither its thief nor its owner can go on
+    /// using it. Unknown, expired and revoked tokens are `Unauthorized`.
+    pub async fn refresh(
+        pool: &PgPool,
+        refresh_token: &str,
+        lifetimes: TokenLifetimes,
+    ) -> AppResult<IssuedSession> {
+        let token_hash = hash_token(refresh_token);
+        let issued = db::transaction(pool, |tx| {
+            Box::pin(async move {
+                let session: Option<Session> = sqlx::query_as(
+                    "SELECT s.id, s.user_id, s.access_jti, s.expires_at, s.revoked_at \
+                     FROM refresh_tokens t JOIN sessions s ON s.id = t.session_id \
+                     WHERE t.token_hash = $1 \
+                     FOR UPDATE OF s",
+                )
+                .bind(&token_hash)
+                .fetch_optional(&mut **tx)
+                .await?;
+
+                let Some(session) = session else {
+                    return Ok(None);
+                };
+                if session.revoked_at.is_some() || session.expires_at <= Utc::now() {
+                    return Ok(None);
+                }
+                // Claimed in the update itself, which waits for and then sees
+                // a concurrent refresh of the same token
+                let claimed = sqlx::query(
+                    "UPDATE refresh_tokens SET rotated_at = now() \
+                     WHERE token_hash = $1 AND rotated_at IS NULL",
+                )
+                .bind(&token_hash)
+                .execute(&mut **tx)
+                .await?
+                .rows_affected();
+                if claimed == 0 {
+                    tracing::warn!(
+                        "Refresh token reused; revoking session {} of user {}",
+                        session.id,
+                        session.user_id
+                    );
+                    // Committed, unlike an `Err`
+                    revoke(&mut **tx, "id = $1", session.id, lifetimes.access).await?;
+                    return Ok(None);
+                }
+
+                // The previous access token is retired along with the refresh
+                // token, so a session only ever has one
+                revoke_jti(&mut **tx, &session.access_jti, expires_at(lifetimes.access)).await?;
+                let access_jti = random_token();
+                sqlx::query("UPDATE sessions SET access_jti = $2, expires_at = $3 WHERE id = $1")
+                    .bind(session.id)
+                    .bind(&access_jti)
+                    .bind(expires_at(lifetimes.refresh))
+                    .execute(&mut **tx)
+                    .await?;
+
+                let refresh_token = insert_refresh_token(tx, session.id).await?;
+                Ok(Some(IssuedSession {
+                    session_id: session.id,
+                    user_id: session.user_id,
+                    access_jti,
+                    refresh_token,
+                }))
+            })
+        })
+        .await?;
+
+        issued.ok_or(AppError::Unauthorized)
+    }
+
+    /// Ends session `id`, revoking its current access token.
+    pub async fn end<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: i32,
+        access_lifetime: Duration,
+    ) -> AppResult<()> {
+        revoke(executor, "id = $1", id, access_lifetime).await
+    }
+
+    /// Ends every session of `user_id`, signing them out everywhere.
+    pub async fn end_all<'e, E: PgExecutor<'e>>(
+        executor: E,
+        user_id: i32,
+        access_lifetime: Duration,
+    ) -> AppResult<()> {
+        revoke(executor, "user_id = $1", user_id, access_lifetime).await
+    }
+
+    /// Revokes the access token `jti` until it expires at `exp`.
+    pub async fn revoke_token<'e, E: PgExecutor<'e>>(
+        executor: E,
+        jti: &str,
+        exp: usize,
+    ) -> AppResult<()> {
+        let expires_at =
+            DateTime::from_timestamp(exp as i64, 0).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        revoke_jti(executor, jti, expires_at).await
+    }
+
+    /// Whether the access token `jti` has been revoked.
+    pub async fn is_revoked<'e, E: PgExecutor<'e>>(executor: E, jti: &str) -> AppResult<bool> {
+        Ok(
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1)")
+                .bind(jti)
+                .fetch_one(executor)
+                .await?,
+        )
+    }
+}
+
+/// Revokes the live sessions matching `condition`, which is bound to `value`
+/// as `$1`, along with their access tokens. The expiry of those is not
+/// stored, so they stay on the list for a full access token lifetime.
+async fn revoke<'e, E: PgExecutor<'e>>(
+    executor: E,
+    condition: &str,
+    value: i32,
+    access_lifetime: Duration,
+) -> AppResult<()> {
+    sqlx::query(&format!(
+        "WITH ended AS ( \
+             UPDATE sessions SET revoked_at = now() \
+             WHERE {} AND revoked_at IS NULL \
+             RETURNING access_jti \
+         ) \
+         INSERT INTO revoked_tokens (jti, expires_at) \
+         SELECT access_jti, $2 FROM ended \
+         ON CONFLICT (jti) DO NOTHING",
+        condition
+    ))
+    .bind(value)
+    .bind(expires_at(access_lifetime))
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn revoke_jti<'e, E: PgExecutor<'e>>(
+    executor: E,
+    jti: &str,
+    expires_at: DateTime<Utc>,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO revoked_tokens (jti, expires_at) VALUES ($1, $2) \
+         ON CONFLICT (jti) DO NOTHING",
+    )
+    .bind(jti)
+    .bind(expires_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn purge_revoked<'e, E: PgExecutor<'e>>(executor: E) -> AppResult<()> {
+    sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < now()")
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+async fn insert_refresh_token(conn: &mut PgConnection, session_id: i32) -> AppResult<String> {
+    let refresh_token = random_token();
+    sqlx::query("INSERT INTO refresh_tokens (token_hash, session_id) VALUES ($1, $2)")
+        .bind(hash_token(&refresh_token))
+        .bind(session_id)
+        .execute(conn)
+        .await?;
+    Ok(refresh_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIFETIMES: TokenLifetimes = TokenLifetimes {
+        access: Duration::from_secs(60 * 60),
+        refresh: Duration::from_secs(24 * 60 * 60),
+        password_reset: Duration::from_secs(60 * 60),
+        email_verification: Duration::from_secs(60 * 60),
+    };
+
+    async fn start(pool: &PgPool) -> IssuedSession {
+        let user_id: i32 = sqlx::query_scalar(
+            "INSERT INTO users (name, email, password) \
+             VALUES ('Alice', 'alice@example.com', 'x') RETURNING id",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        Session::start(pool, user_id, LIFETIMES).await.unwrap()
+    }
+
+    #[sqlx::test]
+    async fn rotates_the_refresh_token(pool: PgPool) {
+        let started = start(&pool).await;
+        let refreshed = Session::refresh(&pool, &started.refresh_token, LIFETIMES)
+            .await
+            .unwrap();
+
+        assert_eq!(refreshed.session_id, started.session_id);
+        assert_ne!(refreshed.refresh_token, started.refresh_token);
+        assert!(Session::is_revoked(&pool, &started.access_jti)
+            .await
+            .unwrap());
+        assert!(!Session::is_revoked(&pool, &refreshed.access_jti)
+            .await
+            .unwrap());
+        Session::refresh(&pool, &refreshed.refresh_token, LIFETIMES)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn reuse_revokes_the_session(pool: PgPool) {
+        let started = start(&pool).await;
+        let refreshed = Session::refresh(&pool, &started.refresh_token, LIFETIMES)
+            .await
+            .unwrap();
+
+        let reused = Session::refresh(&pool, &started.refresh_token, LIFETIMES).await;
+        assert!(matches!(reused, Err(AppError::Unauthorized)));
+        assert!(Session::is_revoked(&pool, &refreshed.access_jti)
+            .await
+            .unwrap());
+        let rotated = Session::refresh(&pool, &refreshed.refresh_token, LIFETIMES).await;
+        assert!(matches!(rotated, Err(AppError::Unauthorized)));
+    }
+
+    #[sqlx::test]
+    async fn concurrent_refreshes_are_taken_as_reuse(pool: PgPool) {
+        let started = start(&pool).await;
+        let (first, second) = tokio::join!(
+            Session::refresh(&pool, &started.refresh_token, LIFETIMES),
+            Session::refresh(&pool, &started.refresh_token, LIFETIMES),
+        );
+
+        let (issued, refused) = match (first, second) {
+            (Ok(issued), refused) | (refused, Ok(issued)) => (issued, refused),
+            (Err(first), Err(second)) => panic!("both refreshes failed: {first:?}, {second:?}"),
+        };
+        assert!(matches!(refused, Err(AppError::Unauthorized)));
+        let rotated = Session::refresh(&pool, &issued.refresh_token, LIFETIMES).await;
+        assert!(matches!(rotated, Err(AppError::Unauthorized)));
+    }
+}
@@ -1,48 +1,224 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use asg_macros::ModelRepository;
+use axum::async_trait;
+
 use crate::error::{AppError, AppResult};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::Serialize;
-use sqlx::{FromRow, PgPool};
+use serde_json::Value;
+use sqlx::{FromRow, PgExecutor, PgPool};
 
-use super::repository::{ModelRepository, PgQuery};
+use super::customers::Customer;
+use super::include::{self, Related, Relation};
+use super::patch::Patch;
+use super::permission_catalogue::PermissionName;
+use super::permissions::Permission;
+use super::repository::ModelRepository;
+use super::roles::{self, Role};
 
-#[derive(Serialize, Debug, FromRow)]
+#[derive(Serialize, Debug, Default, FromRow, ModelRepository)]
+#[sqlx(default)]
+#[model(
+    table = "users",
+    create = UserForCreate,
+    update = UserForUpdate,
+    search_vector = "search_vector",
+)]
 pub struct User {
+    #[model(indexed)]
     pub id: i32,
+    #[model(create, update, search, indexed)]
     pub name: String,
+    #[model(create, update, search, indexed)]
     pub email: String,
+    #[model(update, indexed)]
     pub email_verified_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing)]
+    #[model(create, update)]
     pub password: String,
+    #[model(update, indexed)]
     pub customer_id: Option<i32>,
+    #[model(indexed, version)]
     pub updated_at: Option<DateTime<Utc>>,
+    #[model(indexed)]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing)]
+    #[model(skip)]
     pub remember_token: Option<String>,
 }
 
 pub struct UserForCreate {
     pub name: String,
     pub email: String,
-    pub hashed_password: String,
+    /// Already hashed with `auth::security::hash_password`.
+    pub password: String,
 }
 
+#[derive(Default)]
 pub struct UserForUpdate {
-    pub name: Option<String>,
-    pub email: Option<String>,
-    pub hashed_password: Option<String>,
-    pub customer_id: Option<String>,
+    pub name: Patch<String>,
+    pub email: Patch<String>,
+    /// Cleared when the email address changes, so the new one is verified.
+    pub email_verified_at: Patch<DateTime<Utc>>,
+    /// Already hashed with `auth::security::hash_password`.
+    pub password: Patch<String>,
+    pub customer_id: Patch<i32>,
 }
 
-#[cfg(not(feature = "deploy"))]
 impl User {
-    pub async fn set_email_verified_at(pool: &PgPool, id: i32) -> AppResult<()> {
-        sqlx::query!(
-            "UPDATE users SET email_verified_at = now() WHERE id = $1",
-            id
+    /// The subset of `emails` already taken by some user.
+    pub async fn existing_emails<'e, E: PgExecutor<'e>>(
+        executor: E,
+        emails: &[String],
+    ) -> AppResult<HashSet<String>> {
+        let existing: Vec<String> =
+            sqlx::query_scalar("SELECT email FROM users WHERE email = ANY($1)")
+                .bind(emails)
+                .fetch_all(executor)
+                .await?;
+        Ok(existing.into_iter().collect())
+    }
+
+    pub async fn set_email_verified_at<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: i32,
+    ) -> AppResult<()> {
+        sqlx::query("UPDATE users SET email_verified_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+
+    /// The email address of user `id`, locking the row for the transaction.
+    pub async fn email_for_update<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: i32,
+    ) -> AppResult<Option<String>> {
+        Ok(
+            sqlx::query_scalar("SELECT email FROM users WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(executor)
+                .await?,
+        )
+    }
+
+    /// Whether user `id` has verified their email address; `None` when there
+    /// is no such user.
+    pub async fn is_email_verified<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: i32,
+    ) -> AppResult<Option<bool>> {
+        Ok(
+            sqlx::query_scalar("SELECT email_verified_at IS NOT NULL FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_optional(executor)
+                .await?,
+        )
+    }
+
+    /// Records that a verification link is being sent to user `id`, unless
+    /// one already went out within `throttle`. Returns whether it may be sent.
+    pub async fn mark_verification_sent<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: i32,
+        throttle: Duration,
+    ) -> AppResult<bool> {
+        let cutoff = Utc::now() - TimeDelta::from_std(throttle).unwrap_or_default();
+        let result = sqlx::query(
+            "UPDATE users SET email_verification_sent_at = now() \
+             WHERE id = $1 \
+             AND (email_verification_sent_at IS NULL OR email_verification_sent_at <= $2)",
         )
-        .execute(pool)
+        .bind(id)
+        .bind(cutoff)
+        .execute(executor)
         .await?;
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
+
+    /// Deletes the user unless they are the only remaining admin, returning
+    /// whether the row was deleted.
+    pub async fn delete_unless_last_admin(pool: &PgPool, id: i32) -> AppResult<bool> {
+        let mut tx = pool.begin().await?;
+
+        let admins = roles::lock_admin_ids(&mut tx).await?;
+
+        if admins.contains(&id) && !admins.iter().any(|&admin| admin != id) {
+            return Ok(false);
+        }
+
+        Self::delete(&mut *tx, id).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Related for User {
+    const RELATIONS: &'static [Relation] = &[
+        Relation {
+            name: "roles",
+            permission: PermissionName::RolesView,
+        },
+        Relation {
+            name: "permissions",
+            permission: PermissionName::PermissionsView,
+        },
+        Relation {
+            name: "customer",
+            permission: PermissionName::CustomersView,
+        },
+    ];
+
+    async fn load_relation(pool: &PgPool, rows: &[Self], relation: &str) -> AppResult<Vec<Value>> {
+        let ids = || rows.iter().map(|user| user.id).collect();
+        match relation {
+            "roles" => {
+                include::has_many::<Role>(
+                    pool,
+                    "SELECT r.*, ur.user_id AS owner_id FROM roles r \
+                     JOIN user_has_roles ur ON ur.role_id = r.id \
+                     WHERE ur.user_id = ANY($1) \
+                     ORDER BY r.name",
+                    ids(),
+                )
+                .await
+            }
+            // Held through any of the user's roles, so a permission granted by
+            // two roles is listed once
+            "permissions" => {
+                include::has_many::<Permission>(
+                    pool,
+                    "SELECT DISTINCT p.*, ur.user_id AS owner_id FROM permissions p \
+                     JOIN role_has_permissions rp ON rp.permission_id = p.id \
+                     JOIN user_has_roles ur ON ur.role_id = rp.role_id \
+                     WHERE ur.user_id = ANY($1) \
+                     ORDER BY p.name",
+                    ids(),
+                )
+                .await
+            }
+            "customer" => {
+                include::has_one::<Customer>(
+                    pool,
+                    "SELECT c.*, u.id AS owner_id FROM customers c \
+                     JOIN users u ON u.customer_id = c.id \
+                     WHERE u.id = ANY($1)",
+                    ids(),
+                )
+                .await
+            }
+            _ => Err(include::unknown_relation(relation)),
+        }
+    }
+}
+
+#[cfg(not(feature = "deploy"))]
+impl User {
     pub async fn get_password_hash(pool: &PgPool, email: &str) -> AppResult<(i32, String)> {
         let result = sqlx::query!("SELECT id, password FROM users WHERE email = $1", email)
             .fetch_one(pool)
@@ -55,36 +231,63 @@ impl User {
     }
 }
 
-impl ModelRepository for User {
-    type CreateModel = UserForCreate;
-
-    type UpdateModel = UserForUpdate;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    const TABLE_NAME: &'static str = "users";
-
-    const CREATE_FIELDS: &'static [&'static str] = &["name", "email", "password"];
+    fn row(name: &str, email: &str, password: &str) -> UserForCreate {
+        UserForCreate {
+            name: name.to_string(),
+            email: email.to_string(),
+            password: password.to_string(),
+        }
+    }
 
-    const UPDATE_FIELDS: &'static [&'static str] = &["name", "email", "password", "customer_id"];
+    #[sqlx::test]
+    async fn upsert_updates_names_and_keeps_passwords(pool: PgPool) {
+        User::create(&pool, row("Alice", "alice@example.com", "old-hash"))
+            .await
+            .unwrap();
 
-    const SEARCH_COLUMNS: &'static [&'static str] = &["name", "email"];
+        let users = User::upsert(
+            &pool,
+            vec![
+                row("Alice Liddell", "alice@example.com", "new-hash"),
+                row("Bob", "bob@example.com", "bob-hash"),
+            ],
+            "email",
+            &["name"],
+        )
+        .await
+        .unwrap();
 
-    fn bind_create(query: PgQuery<'_, Self>, data: Self::CreateModel) -> PgQuery<'_, Self> {
-        query
-            .bind(data.name)
-            .bind(data.email)
-            .bind(data.hashed_password)
+        assert_eq!(users.len(), 2);
+        let alice = users
+            .iter()
+            .find(|user| user.email == "alice@example.com")
+            .unwrap();
+        assert_eq!(alice.name, "Alice Liddell");
+        assert_eq!(alice.password, "old-hash");
+        let bob = users
+            .iter()
+            .find(|user| user.email == "bob@example.com")
+            .unwrap();
+        assert_eq!(bob.password, "bob-hash");
     }
 
-    fn bind_update(
-        query: PgQuery<'_, Self>,
-        id: i32,
-        data: Self::UpdateModel,
-    ) -> PgQuery<'_, Self> {
-        query
-            .bind(id)
-            .bind(data.name)
-            .bind(data.email)
-            .bind(data.hashed_password)
-            .bind(data.customer_id)
+    #[sqlx::test]
+    async fn create_many_inserts_every_row(pool: PgPool) {
+        let users = User::create_many(
+            &pool,
+            vec![
+                row("Alice", "alice@example.com", "a"),
+                row("Bob", "bob@example.com", "b"),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let emails: Vec<&str> = users.iter().map(|user| user.email.as_str()).collect();
+        assert_eq!(emails, ["alice@example.com", "bob@example.com"]);
     }
 }
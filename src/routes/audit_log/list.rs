@@ -0,0 +1,30 @@
+use crate::app_state::SharedAppState;
+use crate::model::{audit_log::AuditEntry, repository::ModelRepository, ListOptions};
+use crate::routes::export::Export;
+use crate::routes::response::{self, ApiResult};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// The history of a record (`filter[table_name]=users&filter[row_id]=1`) or
+/// of an actor (`filter[actor_id]=1`), optionally narrowed by `action` and
+/// `created_at`.
+pub async fn list(
+    State(state): State<SharedAppState>,
+    options: ListOptions,
+    Export(export): Export,
+) -> Result<Response, ApiResult<()>> {
+    let result = match export {
+        Some(export) => {
+            export
+                .respond::<AuditEntry>(&state.db_pool, None, options)
+                .await
+        }
+        None => AuditEntry::list(&state.db_pool, &options)
+            .await
+            .map(|list| Json(list).into_response()),
+    };
+    result.map_err(|err| response::error_response(err, "Audit entry not found."))
+}
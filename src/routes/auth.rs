@@ -2,14 +2,20 @@ use axum::{
     extract::{Json, State},
     http::StatusCode,
     response::IntoResponse,
+    Extension,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::routes::response::{self, ApiResponse};
 use crate::{
     app_state::SharedAppState,
-    auth::{self, jwt::generate_jwt},
-    error::AppError,
-    model::users::User,
+    auth::{self, claims::Claims, jwt::generate_jwt},
+    db,
+    error::{AppError, AppResult},
+    model::{
+        sessions::{IssuedSession, Session},
+        users::User,
+    },
 };
 
 #[derive(Debug, Deserialize)]
@@ -18,17 +24,33 @@ pub struct LoginRequest {
     password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum LoginResponse {
-    Success { token: String },
-    Error { error: String },
+    Success {
+        token: String,
+        refresh_token: String,
+        /// Seconds until `token` expires.
+        expires_in: u64,
+    },
+    Error {
+        error: String,
+    },
 }
 
+pub type LogoutResponse = ApiResponse<()>;
+
 impl LoginResponse {
-    pub fn success(token: &str) -> Self {
+    pub fn success(token: &str, refresh_token: &str, expires_in: u64) -> Self {
         LoginResponse::Success {
             token: token.to_string(),
+            refresh_token: refresh_token.to_string(),
+            expires_in,
         }
     }
 
@@ -43,6 +65,23 @@ fn error_response(status: StatusCode, message: &str) -> (StatusCode, Json<LoginR
     (status, Json(LoginResponse::error(message)))
 }
 
+/// Signs an access token for `session` and pairs it with its refresh token.
+fn issue_tokens(state: &SharedAppState, session: IssuedSession) -> AppResult<LoginResponse> {
+    let lifetime = state.token_lifetimes.access;
+    let claims = Claims::new(
+        session.user_id,
+        session.session_id,
+        &session.access_jti,
+        lifetime,
+    )?;
+    let token = generate_jwt(&claims, &state.app_key)?;
+    Ok(LoginResponse::success(
+        &token,
+        &session.refresh_token,
+        lifetime.as_secs(),
+    ))
+}
+
 #[cfg(not(feature = "deploy"))]
 pub async fn login(
     State(state): State<SharedAppState>,
@@ -65,10 +104,13 @@ pub async fn login(
         };
 
     match auth::security::verify_password(&payload.password, &password_hash) {
-        Ok(true) => match generate_jwt(&user_id.to_string(), &state.app_key) {
-            Ok(token) => (StatusCode::OK, Json(LoginResponse::success(&token))),
+        Ok(true) => match Session::start(&state.db_pool, user_id, state.token_lifetimes)
+            .await
+            .and_then(|session| issue_tokens(&state, session))
+        {
+            Ok(response) => (StatusCode::OK, Json(response)),
             Err(e) => {
-                tracing::error!("JWT generation error: {:?}", e);
+                tracing::error!("Token generation error: {:?}", e);
                 generic_error
             }
         },
@@ -79,3 +121,73 @@ pub async fn login(
         }
     }
 }
+
+/// Exchanges a refresh token for a new access token and refresh token.
+pub async fn refresh(
+    State(state): State<SharedAppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> impl IntoResponse {
+    match Session::refresh(
+        &state.db_pool,
+        &payload.refresh_token,
+        state.token_lifetimes,
+    )
+    .await
+    .and_then(|session| issue_tokens(&state, session))
+    {
+        Ok(response) => (StatusCode::OK, Json(response)),
+        Err(AppError::Unauthorized) => {
+            error_response(StatusCode::UNAUTHORIZED, "Invalid or expired refresh token")
+        }
+        Err(e) => {
+            tracing::error!("Token refresh error: {:?}", e);
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "An unexpected error occurred",
+            )
+        }
+    }
+}
+
+/// Ends the caller's session and revokes the token it was made with.
+pub async fn logout(
+    State(state): State<SharedAppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    let access_lifetime = state.token_lifetimes.access;
+    let result = db::transaction(&state.db_pool, |tx| {
+        Box::pin(async move {
+            Session::end(&mut **tx, claims.sid, access_lifetime).await?;
+            Session::revoke_token(&mut **tx, &claims.jti, claims.exp).await
+        })
+    })
+    .await;
+
+    match result {
+        Ok(()) => response::ok("Logged out successfully.", ()),
+        Err(err) => response::error_response(err, "Session not found."),
+    }
+}
+
+/// Ends every session of the caller, on all devices.
+pub async fn logout_all(
+    State(state): State<SharedAppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    let Some(user_id) = claims.user_id() else {
+        return response::unexpected_error();
+    };
+    let access_lifetime = state.token_lifetimes.access;
+    let result = db::transaction(&state.db_pool, |tx| {
+        Box::pin(async move {
+            Session::end_all(&mut **tx, user_id, access_lifetime).await?;
+            Session::revoke_token(&mut **tx, &claims.jti, claims.exp).await
+        })
+    })
+    .await;
+
+    match result {
+        Ok(()) => response::ok("Logged out of all sessions.", ()),
+        Err(err) => response::error_response(err, "Session not found."),
+    }
+}
@@ -0,0 +1,76 @@
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::model::etag::{ETag, ETagCondition};
+
+/// The request's `If-Match` header, if any.
+#[derive(Debug, Default)]
+pub struct IfMatch(pub Option<ETagCondition>);
+
+/// The request's `If-None-Match` header, if any.
+#[derive(Debug, Default)]
+pub struct IfNoneMatch(pub Option<ETagCondition>);
+
+fn condition(headers: &HeaderMap, name: HeaderName) -> Option<ETagCondition> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| ETagCondition::parse(&values.join(",")))
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfMatch(condition(&parts.headers, header::IF_MATCH)))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfNoneMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfNoneMatch(condition(
+            &parts.headers,
+            header::IF_NONE_MATCH,
+        )))
+    }
+}
+
+impl IfMatch {
+    pub fn condition(&self) -> Option<&ETagCondition> {
+        self.0.as_ref()
+    }
+}
+
+impl IfNoneMatch {
+    /// Answers a GET for a row at version `etag`: `304 Not Modified` when the
+    /// client already holds it, otherwise `response` with an `ETag` header.
+    pub fn respond(&self, etag: Option<ETag>, response: impl IntoResponse) -> Response {
+        let not_modified = self
+            .0
+            .as_ref()
+            .is_some_and(|condition| condition.matches_weak(etag.as_ref()));
+        let header = etag.and_then(|etag| HeaderValue::from_str(&etag.to_string()).ok());
+
+        let mut response = if not_modified {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            response.into_response()
+        };
+        if let Some(header) = header {
+            response.headers_mut().insert(header::ETAG, header);
+        }
+        response
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::{
+    app_state::SharedAppState,
+    model::{
+        contacts::{Contact, ContactForCreate, AT_LEAST_ONE_FIELD_CONSTRAINT, IDENTIFYING_FIELDS},
+        customers::Customer,
+        repository::ModelRepository,
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+pub type CreateContactResponse = ApiResponse<Contact>;
+
+pub(crate) const IDENTIFYING_FIELD_REQUIRED: &str =
+    "At least one of first name, last name, phone or email is required.";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateContactRequest {
+    #[validate(length(max = 100, message = "First name must be at most 100 characters"))]
+    first_name: Option<String>,
+
+    #[validate(length(max = 100, message = "Last name must be at most 100 characters"))]
+    last_name: Option<String>,
+
+    #[validate(length(max = 100, message = "Position must be at most 100 characters"))]
+    position: Option<String>,
+
+    #[validate(length(max = 30, message = "Phone must be at most 30 characters"))]
+    phone: Option<String>,
+
+    #[validate(email(message = "Email must be a valid email address"))]
+    email: Option<String>,
+}
+
+impl CreateContactRequest {
+    pub(crate) fn for_customer(self, customer_id: i32) -> ContactForCreate {
+        ContactForCreate {
+            first_name: self.first_name,
+            last_name: self.last_name,
+            position: self.position,
+            phone: self.phone,
+            email: self.email,
+            customer_id,
+        }
+    }
+
+    /// Mirrors the CHECK constraint, for payloads checked before any insert.
+    pub(crate) fn has_identifying_field(&self) -> bool {
+        self.first_name.is_some()
+            || self.last_name.is_some()
+            || self.phone.is_some()
+            || self.email.is_some()
+    }
+}
+
+/// Reports the contacts CHECK constraint as a field error rather than a 500.
+pub(crate) fn contact_error_response<T>(err: AppError) -> ApiResult<T> {
+    if err.constraint() == Some(AT_LEAST_ONE_FIELD_CONSTRAINT) {
+        let errors = IDENTIFYING_FIELDS
+            .iter()
+            .map(|field| {
+                (
+                    field.to_string(),
+                    vec![IDENTIFYING_FIELD_REQUIRED.to_string()],
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        return response::validation_failed(errors);
+    }
+
+    response::error_response(err, "Contact not found.")
+}
+
+pub async fn create(
+    State(state): State<SharedAppState>,
+    Path(customer_id): Path<i32>,
+    payload: Result<Json<CreateContactRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    if let Err(err) = Customer::get(&state.db_pool, customer_id).await {
+        return response::error_response(err, "Customer not found.");
+    }
+
+    match Contact::create(&state.db_pool, payload.for_customer(customer_id)).await {
+        Ok(contact) => response::created("Contact created successfully.", contact),
+        Err(err) => contact_error_response(err),
+    }
+}
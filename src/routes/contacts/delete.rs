@@ -0,0 +1,18 @@
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    model::{contacts::Contact, repository::ModelRepository},
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+
+pub type DeleteContactResponse = ApiResponse<()>;
+
+pub async fn delete(State(state): State<SharedAppState>, Path(id): Path<i32>) -> impl IntoResponse {
+    match Contact::delete(&state.db_pool, id).await {
+        Ok(()) => response::ok("Contact deleted successfully.", ()),
+        Err(err) => response::error_response(err, "Contact not found."),
+    }
+}
@@ -0,0 +1,50 @@
+use crate::app_state::SharedAppState;
+use crate::model::{
+    contacts::Contact,
+    fields::{Fields, Sparse},
+    repository::ModelRepository,
+};
+use crate::routes::conditional::IfNoneMatch;
+use crate::routes::response;
+use axum::http::StatusCode;
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub enum GetContactResponse {
+    Success(Sparse<Contact>),
+    Error { error: String },
+}
+
+pub async fn get(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    Query(fields): Query<Fields>,
+    if_none_match: IfNoneMatch,
+) -> Response {
+    let fields = match fields.field_set(Contact::FIELDS) {
+        Ok(fields) => fields,
+        Err(err) => {
+            return response::error_response::<()>(err, "Contact not found.").into_response()
+        }
+    };
+
+    match Contact::get_sparse(&state.db_pool, id, fields.as_ref()).await {
+        Ok(data) => {
+            // A sparse fieldset changes the representation, not the version
+            let etag = fields.is_none().then(|| data.etag()).flatten();
+            if_none_match.respond(
+                etag,
+                (
+                    StatusCode::OK,
+                    Json(GetContactResponse::Success(Sparse { row: data, fields })),
+                ),
+            )
+        }
+        Err(err) => response::error_response::<()>(err, "Contact not found.").into_response(),
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use axum::{body::Bytes, extract::State, response::IntoResponse};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+use crate::routes::import::{
+    validate_row, ColumnMapping, Import, ImportField, ImportOptions, ImportReport,
+};
+use crate::routes::response::{self, ApiResponse, FieldErrors};
+use crate::{
+    app_state::SharedAppState,
+    error::AppResult,
+    model::{
+        contacts::{Contact, ContactForCreate},
+        customers::Customer,
+        repository::ModelRepository,
+    },
+};
+
+use super::create::{contact_error_response, CreateContactRequest, IDENTIFYING_FIELD_REQUIRED};
+
+pub type ImportContactsResponse = ApiResponse<ImportReport>;
+
+/// One contact per row, for the customer whose id is in `customer_id`.
+pub const FIELDS: &[ImportField] = &[
+    ImportField::int("customer_id").required(),
+    ImportField::text("first_name"),
+    ImportField::text("last_name"),
+    ImportField::text("position"),
+    ImportField::text("phone"),
+    ImportField::text("email"),
+];
+
+/// Imports contacts from `csv`, validating each row as
+/// `POST /customers/:id/contacts` would. The valid rows are saved in one
+/// transaction unless `dry_run`.
+pub async fn import_contacts(
+    pool: &PgPool,
+    csv: &[u8],
+    mapping: &ColumnMapping,
+    dry_run: bool,
+) -> AppResult<ImportReport> {
+    let mut import = Import::parse(csv, mapping, FIELDS)?;
+    let mut rows = import.check(check_row);
+
+    let customer_ids: Vec<i32> = rows.iter().map(|(_, row)| row.customer_id).collect();
+    let existing = Customer::existing_ids(pool, &customer_ids).await?;
+    rows.retain(|(row, contact)| {
+        if existing.contains(&contact.customer_id) {
+            return true;
+        }
+        import.reject(
+            *row,
+            HashMap::from([(
+                "customer_id".to_string(),
+                vec!["Customer not found.".to_string()],
+            )]),
+        );
+        false
+    });
+
+    let valid = rows.len();
+    if dry_run || rows.is_empty() {
+        return Ok(import.report(dry_run, valid, 0));
+    }
+
+    // `create_many` writes every row in one transaction
+    let rows = rows.into_iter().map(|(_, row)| row).collect();
+    Contact::create_many(pool, rows).await?;
+
+    Ok(import.report(dry_run, valid, valid))
+}
+
+fn check_row(mut values: Map<String, Value>) -> Result<ContactForCreate, FieldErrors> {
+    let customer_id = values.remove("customer_id").and_then(|id| id.as_i64());
+
+    let mut errors = FieldErrors::new();
+    let request = match validate_row::<CreateContactRequest>(values) {
+        Ok(request) => {
+            if !request.has_identifying_field() {
+                errors.insert(
+                    "general".to_string(),
+                    vec![IDENTIFYING_FIELD_REQUIRED.to_string()],
+                );
+            }
+            Some(request)
+        }
+        Err(row_errors) => {
+            errors.extend(row_errors);
+            None
+        }
+    };
+
+    let customer_id = match customer_id.map(i32::try_from) {
+        Some(Ok(customer_id)) => Some(customer_id),
+        Some(Err(_)) => {
+            errors.insert(
+                "customer_id".to_string(),
+                vec!["Customer not found.".to_string()],
+            );
+            None
+        }
+        None => {
+            errors.insert(
+                "customer_id".to_string(),
+                vec!["This field is required.".to_string()],
+            );
+            None
+        }
+    };
+
+    match (request, customer_id) {
+        (Some(request), Some(customer_id)) if errors.is_empty() => {
+            Ok(request.for_customer(customer_id))
+        }
+        _ => Err(errors),
+    }
+}
+
+pub async fn import(
+    State(state): State<SharedAppState>,
+    options: ImportOptions,
+    body: Bytes,
+) -> impl IntoResponse {
+    match import_contacts(&state.db_pool, &body, &options.mapping, options.dry_run).await {
+        Ok(report) => response::ok(&report.message(), report),
+        Err(err) => contact_error_response(err),
+    }
+}
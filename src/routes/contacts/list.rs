@@ -0,0 +1,35 @@
+use crate::app_state::SharedAppState;
+use crate::model::{
+    contacts::Contact, customers::Customer, repository::ModelRepository, ListOptions,
+};
+use crate::routes::export::Export;
+use crate::routes::response::{self, ApiResult};
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+pub async fn list(
+    State(state): State<SharedAppState>,
+    Path(customer_id): Path<i32>,
+    options: ListOptions,
+    Export(export): Export,
+) -> Result<Response, ApiResult<()>> {
+    Customer::get(&state.db_pool, customer_id)
+        .await
+        .map_err(|err| response::error_response(err, "Customer not found."))?;
+
+    let scope = ("customer_id", customer_id);
+    let result = match export {
+        Some(export) => {
+            export
+                .respond::<Contact>(&state.db_pool, Some(scope), options)
+                .await
+        }
+        None => Contact::list_by(&state.db_pool, scope.0, scope.1, &options)
+            .await
+            .map(|list| Json(list).into_response()),
+    };
+    result.map_err(|err| response::error_response(err, "Customer not found."))
+}
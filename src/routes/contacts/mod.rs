@@ -0,0 +1,13 @@
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod import;
+pub mod list;
+pub mod update;
+
+pub use create::create;
+pub use delete::delete;
+pub use get::get;
+pub use import::import;
+pub use list::list;
+pub use update::{patch, update};
@@ -0,0 +1,118 @@
+use crate::routes::conditional::IfMatch;
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    model::{
+        contacts::{Contact, ContactForUpdate},
+        patch::Patch,
+        repository::ModelRepository,
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use super::create::contact_error_response;
+
+pub type UpdateContactResponse = ApiResponse<Contact>;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateContactRequest {
+    #[validate(length(max = 100, message = "First name must be at most 100 characters"))]
+    first_name: Option<String>,
+
+    #[validate(length(max = 100, message = "Last name must be at most 100 characters"))]
+    last_name: Option<String>,
+
+    #[validate(length(max = 100, message = "Position must be at most 100 characters"))]
+    position: Option<String>,
+
+    #[validate(length(max = 30, message = "Phone must be at most 30 characters"))]
+    phone: Option<String>,
+
+    #[validate(email(message = "Email must be a valid email address"))]
+    email: Option<String>,
+}
+
+pub async fn update(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<UpdateContactRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let contact_for_update = ContactForUpdate {
+        first_name: payload.first_name.into(),
+        last_name: payload.last_name.into(),
+        position: payload.position.into(),
+        phone: payload.phone.into(),
+        email: payload.email.into(),
+    };
+
+    match Contact::update_if_match(&state.db_pool, id, contact_for_update, if_match.condition())
+        .await
+    {
+        Ok(contact) => response::ok("Contact updated successfully.", contact),
+        Err(err) => contact_error_response(err),
+    }
+}
+
+/// Body of `PATCH /contacts/:id`: only the fields sent are changed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchContactRequest {
+    #[serde(default)]
+    #[validate(length(max = 100, message = "First name must be at most 100 characters"))]
+    first_name: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 100, message = "Last name must be at most 100 characters"))]
+    last_name: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 100, message = "Position must be at most 100 characters"))]
+    position: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 30, message = "Phone must be at most 30 characters"))]
+    phone: Patch<String>,
+
+    #[serde(default)]
+    #[validate(email(message = "Email must be a valid email address"))]
+    email: Patch<String>,
+}
+
+pub async fn patch(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<PatchContactRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let contact_for_update = ContactForUpdate {
+        first_name: payload.first_name,
+        last_name: payload.last_name,
+        position: payload.position,
+        phone: payload.phone,
+        email: payload.email,
+    };
+
+    match Contact::update_if_match(&state.db_pool, id, contact_for_update, if_match.condition())
+        .await
+    {
+        Ok(contact) => response::ok("Contact updated successfully.", contact),
+        Err(err) => contact_error_response(err),
+    }
+}
@@ -0,0 +1,174 @@
+use crate::routes::contacts::create::{
+    contact_error_response, CreateContactRequest, IDENTIFYING_FIELD_REQUIRED,
+};
+use crate::routes::response::{self, ApiResponse, FieldErrors};
+use crate::{
+    app_state::SharedAppState,
+    db,
+    error::AppResult,
+    model::{
+        contacts::Contact,
+        customers::{Customer, CustomerForCreate, CustomerForUpdate},
+        patch::Patch,
+        repository::ModelRepository,
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use validator::Validate;
+
+pub type CreateCustomerResponse = ApiResponse<CreatedCustomer>;
+
+#[derive(Debug, Serialize)]
+pub struct CreatedCustomer {
+    #[serde(flatten)]
+    pub customer: Customer,
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCustomerRequest {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Name must be between 1 and 255 characters"
+    ))]
+    name: String,
+
+    #[validate(length(max = 255, message = "Address must be at most 255 characters"))]
+    address: Option<String>,
+
+    #[validate(length(max = 255, message = "Address line 2 must be at most 255 characters"))]
+    address_2: Option<String>,
+
+    #[validate(length(max = 100, message = "Suburb must be at most 100 characters"))]
+    suburb: Option<String>,
+
+    #[validate(length(max = 50, message = "State must be at most 50 characters"))]
+    state: Option<String>,
+
+    #[validate(length(max = 10, message = "Postcode must be at most 10 characters"))]
+    postcode: Option<String>,
+
+    #[serde(default = "default_terms")]
+    #[validate(range(min = 0, max = 365, message = "Terms must be between 0 and 365 days"))]
+    terms: i32,
+
+    #[validate(range(min = 0, message = "Credit limit cannot be negative"))]
+    credit_limit: Option<i32>,
+
+    #[serde(default)]
+    active: bool,
+
+    /// Contacts created together with the customer.
+    #[serde(default)]
+    #[validate(nested)]
+    contacts: Vec<CreateContactRequest>,
+
+    /// Index into `contacts` of the contact to make preferred.
+    preferred_contact: Option<usize>,
+}
+
+pub(crate) fn default_terms() -> i32 {
+    15
+}
+
+impl From<CreateCustomerRequest> for CustomerForCreate {
+    fn from(request: CreateCustomerRequest) -> Self {
+        CustomerForCreate {
+            name: request.name,
+            address: request.address,
+            address_2: request.address_2,
+            suburb: request.suburb,
+            state: request.state,
+            postcode: request.postcode,
+            terms: request.terms,
+            credit_limit: request.credit_limit,
+            active: request.active,
+        }
+    }
+}
+
+impl CreateCustomerRequest {
+    /// Checks that need more than one field, run after `validate`.
+    pub(crate) fn check(&self) -> FieldErrors {
+        let mut errors = FieldErrors::new();
+
+        for (index, contact) in self.contacts.iter().enumerate() {
+            if !contact.has_identifying_field() {
+                errors.insert(
+                    format!("contacts[{}]", index),
+                    vec![IDENTIFYING_FIELD_REQUIRED.to_string()],
+                );
+            }
+        }
+        if self
+            .preferred_contact
+            .is_some_and(|index| index >= self.contacts.len())
+        {
+            errors.insert(
+                "preferred_contact".to_string(),
+                vec!["The preferred contact must be one of the contacts.".to_string()],
+            );
+        }
+
+        errors
+    }
+
+    pub(crate) fn has_contacts(&self) -> bool {
+        !self.contacts.is_empty()
+    }
+
+    /// Creates the customer and its contacts; run it inside a transaction.
+    pub(crate) async fn insert(mut self, conn: &mut PgConnection) -> AppResult<CreatedCustomer> {
+        let contacts = std::mem::take(&mut self.contacts);
+        let preferred_contact = self.preferred_contact;
+
+        let mut customer = Customer::create(&mut *conn, self.into()).await?;
+
+        let mut created = Vec::with_capacity(contacts.len());
+        for contact in contacts {
+            created.push(Contact::create(&mut *conn, contact.for_customer(customer.id)).await?);
+        }
+
+        if let Some(index) = preferred_contact {
+            let customer_for_update = CustomerForUpdate {
+                preferred_contact_id: Patch::Value(created[index].id),
+                ..Default::default()
+            };
+            customer = Customer::update(&mut *conn, customer.id, customer_for_update).await?;
+        }
+
+        Ok(CreatedCustomer {
+            customer,
+            contacts: created,
+        })
+    }
+}
+
+pub async fn create(
+    State(state): State<SharedAppState>,
+    payload: Result<Json<CreateCustomerRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let errors = payload.check();
+    if !errors.is_empty() {
+        return response::validation_failed(errors);
+    }
+
+    let result = db::transaction(&state.db_pool, |tx| {
+        Box::pin(async move { payload.insert(tx).await })
+    })
+    .await;
+
+    match result {
+        Ok(created) => response::created("Customer created successfully.", created),
+        Err(err) => contact_error_response(err),
+    }
+}
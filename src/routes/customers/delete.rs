@@ -0,0 +1,35 @@
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    model::{customers::Customer, repository::ModelRepository},
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+
+pub type DeleteCustomerResponse = ApiResponse<()>;
+
+pub async fn delete(State(state): State<SharedAppState>, Path(id): Path<i32>) -> impl IntoResponse {
+    match Customer::delete(&state.db_pool, id).await {
+        Ok(()) => response::ok("Customer archived successfully.", ()),
+        Err(err) => response::error_response(err, "Customer not found."),
+    }
+}
+
+pub async fn restore(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    match Customer::restore(&state.db_pool, id).await {
+        Ok(customer) => response::ok("Customer restored successfully.", customer),
+        Err(err) => response::error_response(err, "Archived customer not found."),
+    }
+}
+
+pub async fn purge(State(state): State<SharedAppState>, Path(id): Path<i32>) -> impl IntoResponse {
+    match Customer::purge(&state.db_pool, id).await {
+        Ok(()) => response::ok("Customer permanently deleted.", ()),
+        Err(err) => response::error_response(err, "Customer not found."),
+    }
+}
@@ -0,0 +1,195 @@
+use axum::{body::Bytes, extract::State, response::IntoResponse};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+use crate::routes::contacts::create::contact_error_response;
+use crate::routes::import::{
+    validate_row, ColumnMapping, Import, ImportField, ImportOptions, ImportReport,
+};
+use crate::routes::response::{self, ApiResponse, FieldErrors};
+use crate::{
+    app_state::SharedAppState,
+    db,
+    error::AppResult,
+    model::{
+        customers::{Customer, CustomerForCreate},
+        repository::ModelRepository,
+    },
+};
+
+use super::create::CreateCustomerRequest;
+
+pub type ImportCustomersResponse = ApiResponse<ImportReport>;
+
+/// Prefix of the columns holding the row's contact.
+const CONTACT_PREFIX: &str = "contact.";
+
+/// One customer per row. A row may also carry a contact in the `contact.*`
+/// fields, which becomes the customer's preferred contact.
+pub const FIELDS: &[ImportField] = &[
+    ImportField::text("name").required(),
+    ImportField::text("address"),
+    ImportField::text("address_2"),
+    ImportField::text("suburb"),
+    ImportField::text("state"),
+    ImportField::text("postcode"),
+    ImportField::int("terms"),
+    ImportField::int("credit_limit"),
+    ImportField::bool("active"),
+    ImportField::text("contact.first_name"),
+    ImportField::text("contact.last_name"),
+    ImportField::text("contact.position"),
+    ImportField::text("contact.phone"),
+    ImportField::text("contact.email"),
+];
+
+/// Imports customers from `csv`, validating each row as `POST /customers`
+/// would. The valid rows are saved in one transaction unless `dry_run`.
+pub async fn import_customers(
+    pool: &PgPool,
+    csv: &[u8],
+    mapping: &ColumnMapping,
+    dry_run: bool,
+) -> AppResult<ImportReport> {
+    let mut import = Import::parse(csv, mapping, FIELDS)?;
+    let requests = import.check(check_row);
+
+    let valid = requests.len();
+    if dry_run || requests.is_empty() {
+        return Ok(import.report(dry_run, valid, 0));
+    }
+
+    // Customers without a contact need no follow-up writes, so they are
+    // inserted in bulk
+    let (with_contacts, without): (Vec<_>, Vec<_>) = requests
+        .into_iter()
+        .map(|(_, request)| request)
+        .partition(CreateCustomerRequest::has_contacts);
+    let without = without.into_iter().map(CustomerForCreate::from).collect();
+    db::transaction(pool, |tx| {
+        Box::pin(async move {
+            Customer::create_many(&mut **tx, without).await?;
+            for request in with_contacts {
+                request.insert(tx).await?;
+            }
+            Ok(())
+        })
+    })
+    .await?;
+
+    Ok(import.report(dry_run, valid, valid))
+}
+
+fn check_row(values: Map<String, Value>) -> Result<CreateCustomerRequest, FieldErrors> {
+    let mut customer = Map::new();
+    let mut contact = Map::new();
+    for (field, value) in values {
+        match field.strip_prefix(CONTACT_PREFIX) {
+            Some(field) => contact.insert(field.to_string(), value),
+            None => customer.insert(field, value),
+        };
+    }
+    if !contact.is_empty() {
+        customer.insert("contacts".to_string(), Value::from(vec![contact]));
+        customer.insert("preferred_contact".to_string(), Value::from(0));
+    }
+
+    let request: CreateCustomerRequest = validate_row(customer).map_err(contact_fields)?;
+    let errors = request.check();
+    if !errors.is_empty() {
+        return Err(contact_fields(errors));
+    }
+    Ok(request)
+}
+
+/// Names errors in the row's contact after its `contact.*` fields.
+fn contact_fields(errors: FieldErrors) -> FieldErrors {
+    errors
+        .into_iter()
+        .map(|(field, messages)| {
+            let field = match field.strip_prefix("contacts[0]") {
+                Some("") => "contact".to_string(),
+                Some(rest) => format!("contact{}", rest),
+                None => field,
+            };
+            (field, messages)
+        })
+        .collect()
+}
+
+pub async fn import(
+    State(state): State<SharedAppState>,
+    options: ImportOptions,
+    body: Bytes,
+) -> impl IntoResponse {
+    match import_customers(&state.db_pool, &body, &options.mapping, options.dry_run).await {
+        Ok(report) => response::ok(&report.message(), report),
+        Err(err) => contact_error_response(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &[u8] = b"name,terms,contact.first_name,contact.last_name\n\
+        Acme,30,Alice,Smith\n\
+        Globex,soon,,\n\
+        Initech,,,\n";
+
+    async fn customer_names(pool: &PgPool) -> Vec<String> {
+        sqlx::query_scalar("SELECT name FROM customers ORDER BY name")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn dry_run_saves_nothing(pool: PgPool) {
+        let report = import_customers(&pool, CSV, &ColumnMapping::new(), true)
+            .await
+            .unwrap();
+
+        assert_eq!((report.rows, report.valid, report.imported), (3, 2, 0));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row, 3);
+        assert!(report.errors[0].errors.contains_key("terms"));
+        assert!(customer_names(&pool).await.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn imports_the_valid_rows(pool: PgPool) {
+        let report = import_customers(&pool, CSV, &ColumnMapping::new(), false)
+            .await
+            .unwrap();
+
+        assert_eq!((report.rows, report.valid, report.imported), (3, 2, 2));
+        assert_eq!(customer_names(&pool).await, ["Acme", "Initech"]);
+        let preferred: Option<String> = sqlx::query_scalar(
+            "SELECT c.first_name FROM customers cu \
+             JOIN contacts c ON c.id = cu.preferred_contact_id WHERE cu.name = 'Acme'",
+        )
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+        assert_eq!(preferred.as_deref(), Some("Alice"));
+    }
+
+    #[sqlx::test]
+    async fn a_failed_write_saves_nothing(pool: PgPool) {
+        sqlx::raw_sql(
+            "CREATE FUNCTION fail_contact() RETURNS trigger AS $$ \
+             BEGIN RAISE EXCEPTION 'contact refused'; END $$ LANGUAGE plpgsql; \
+             CREATE TRIGGER fail_contact BEFORE INSERT ON contacts \
+             FOR EACH ROW EXECUTE FUNCTION fail_contact();",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let result = import_customers(&pool, CSV, &ColumnMapping::new(), false).await;
+
+        assert!(result.is_err());
+        assert!(customer_names(&pool).await.is_empty());
+    }
+}
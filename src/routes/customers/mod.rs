@@ -0,0 +1,15 @@
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod import;
+pub mod list;
+pub mod preferred_contact;
+pub mod update;
+
+pub use create::create;
+pub use delete::{delete, purge, restore};
+pub use get::get;
+pub use import::import;
+pub use list::list;
+pub use preferred_contact::set_preferred_contact;
+pub use update::{patch, update};
@@ -0,0 +1,72 @@
+use crate::routes::conditional::IfMatch;
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    model::{
+        contacts::Contact,
+        customers::{Customer, CustomerForUpdate},
+        repository::ModelRepository,
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+pub type SetPreferredContactResponse = ApiResponse<Customer>;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetPreferredContactRequest {
+    /// The contact to prefer, or `null` to clear the preferred contact.
+    contact_id: Option<i32>,
+}
+
+pub async fn set_preferred_contact(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<SetPreferredContactRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    if let Err(err) = Customer::get(&state.db_pool, id).await {
+        return response::error_response(err, "Customer not found.");
+    }
+
+    if let Some(contact_id) = payload.contact_id {
+        match Contact::belongs_to_customer(&state.db_pool, contact_id, id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return response::field_error(
+                    "contact_id",
+                    "The contact does not belong to this customer.",
+                )
+            }
+            Err(err) => return response::error_response(err, "Customer not found."),
+        }
+    }
+
+    let customer_for_update = CustomerForUpdate {
+        preferred_contact_id: payload.contact_id.into(),
+        ..Default::default()
+    };
+
+    match Customer::update_if_match(
+        &state.db_pool,
+        id,
+        customer_for_update,
+        if_match.condition(),
+    )
+    .await
+    {
+        Ok(customer) => response::ok("Preferred contact updated successfully.", customer),
+        Err(err) => response::error_response(err, "Customer not found."),
+    }
+}
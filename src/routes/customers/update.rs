@@ -0,0 +1,214 @@
+use crate::routes::conditional::IfMatch;
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    model::{
+        contacts::Contact,
+        customers::{Customer, CustomerForUpdate},
+        patch::{not_null, Patch},
+        repository::ModelRepository,
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use super::create::default_terms;
+
+pub type UpdateCustomerResponse = ApiResponse<Customer>;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCustomerRequest {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Name must be between 1 and 255 characters"
+    ))]
+    name: String,
+
+    #[validate(length(max = 255, message = "Address must be at most 255 characters"))]
+    address: Option<String>,
+
+    #[validate(length(max = 255, message = "Address line 2 must be at most 255 characters"))]
+    address_2: Option<String>,
+
+    #[validate(length(max = 100, message = "Suburb must be at most 100 characters"))]
+    suburb: Option<String>,
+
+    #[validate(length(max = 50, message = "State must be at most 50 characters"))]
+    state: Option<String>,
+
+    #[validate(length(max = 10, message = "Postcode must be at most 10 characters"))]
+    postcode: Option<String>,
+
+    #[serde(default = "default_terms")]
+    #[validate(range(min = 0, max = 365, message = "Terms must be between 0 and 365 days"))]
+    terms: i32,
+
+    #[validate(range(min = 0, message = "Credit limit cannot be negative"))]
+    credit_limit: Option<i32>,
+
+    #[serde(default)]
+    active: bool,
+
+    preferred_contact_id: Option<i32>,
+}
+
+impl From<UpdateCustomerRequest> for CustomerForUpdate {
+    fn from(request: UpdateCustomerRequest) -> Self {
+        CustomerForUpdate {
+            name: Patch::Value(request.name),
+            address: request.address.into(),
+            address_2: request.address_2.into(),
+            suburb: request.suburb.into(),
+            state: request.state.into(),
+            postcode: request.postcode.into(),
+            terms: Patch::Value(request.terms),
+            credit_limit: request.credit_limit.into(),
+            active: Patch::Value(request.active),
+            preferred_contact_id: request.preferred_contact_id.into(),
+        }
+    }
+}
+
+/// Body of `PATCH /customers/:id`: only the fields sent are changed, and
+/// `null` clears a nullable column.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchCustomerRequest {
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Name cannot be null"),
+        length(
+            min = 1,
+            max = 255,
+            message = "Name must be between 1 and 255 characters"
+        )
+    )]
+    name: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 255, message = "Address must be at most 255 characters"))]
+    address: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 255, message = "Address line 2 must be at most 255 characters"))]
+    address_2: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 100, message = "Suburb must be at most 100 characters"))]
+    suburb: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 50, message = "State must be at most 50 characters"))]
+    state: Patch<String>,
+
+    #[serde(default)]
+    #[validate(length(max = 10, message = "Postcode must be at most 10 characters"))]
+    postcode: Patch<String>,
+
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Terms cannot be null"),
+        range(min = 0, max = 365, message = "Terms must be between 0 and 365 days")
+    )]
+    terms: Patch<i32>,
+
+    #[serde(default)]
+    #[validate(range(min = 0, message = "Credit limit cannot be negative"))]
+    credit_limit: Patch<i32>,
+
+    #[serde(default)]
+    #[validate(custom(function = "not_null", message = "Active cannot be null"))]
+    active: Patch<bool>,
+
+    #[serde(default)]
+    preferred_contact_id: Patch<i32>,
+}
+
+impl From<PatchCustomerRequest> for CustomerForUpdate {
+    fn from(request: PatchCustomerRequest) -> Self {
+        CustomerForUpdate {
+            name: request.name,
+            address: request.address,
+            address_2: request.address_2,
+            suburb: request.suburb,
+            state: request.state,
+            postcode: request.postcode,
+            terms: request.terms,
+            credit_limit: request.credit_limit,
+            active: request.active,
+            preferred_contact_id: request.preferred_contact_id,
+        }
+    }
+}
+
+pub async fn update(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<UpdateCustomerRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = check_preferred_contact(&state, id, payload.preferred_contact_id).await {
+        return response;
+    }
+
+    match Customer::update_if_match(&state.db_pool, id, payload.into(), if_match.condition()).await
+    {
+        Ok(customer) => response::ok("Customer updated successfully.", customer),
+        Err(err) => response::error_response(err, "Customer not found."),
+    }
+}
+
+pub async fn patch(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<PatchCustomerRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    if let Some(&contact_id) = payload.preferred_contact_id.value() {
+        if let Err(response) = check_preferred_contact(&state, id, Some(contact_id)).await {
+            return response;
+        }
+    }
+
+    match Customer::update_if_match(&state.db_pool, id, payload.into(), if_match.condition()).await
+    {
+        Ok(customer) => response::ok("Customer updated successfully.", customer),
+        Err(err) => response::error_response(err, "Customer not found."),
+    }
+}
+
+/// Rejects a preferred contact that belongs to some other customer.
+async fn check_preferred_contact(
+    state: &SharedAppState,
+    id: i32,
+    contact_id: Option<i32>,
+) -> Result<(), response::ApiResult<Customer>> {
+    let Some(contact_id) = contact_id else {
+        return Ok(());
+    };
+
+    match Contact::belongs_to_customer(&state.db_pool, contact_id, id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(response::field_error(
+            "preferred_contact_id",
+            "The contact does not belong to this customer.",
+        )),
+        Err(err) => Err(response::error_response(err, "Customer not found.")),
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+
+use crate::routes::response::{self, ApiResponse};
+use crate::{
+    app_state::SharedAppState,
+    auth::{claims::Claims, verification::VerificationLink},
+    error::{AppError, AppResult},
+    mail::{
+        self,
+        templates::{self, describe_lifetime, Variables},
+        Message,
+    },
+    model::{repository::ModelRepository, users::User},
+};
+
+pub type VerifyEmailResponse = ApiResponse<()>;
+pub type ResendVerificationResponse = ApiResponse<()>;
+
+/// How long a user has to wait before asking for another verification link.
+const RESEND_THROTTLE: Duration = Duration::from_secs(60);
+
+/// The email with `user`'s link to `/email/verify`.
+fn verification_message(state: &SharedAppState, user: &User) -> AppResult<Message> {
+    let lifetime = state.token_lifetimes.email_verification;
+    let link = VerificationLink::new(&state.app_key, user.id, &user.email, lifetime)?;
+    let variables = Variables::from([
+        ("name", user.name.clone()),
+        ("url", link.url(&state.app_url)),
+        ("expires_in", describe_lifetime(lifetime)),
+    ]);
+    templates::VERIFY_EMAIL.render(&user.email, &variables)
+}
+
+/// Mails a verification link to a user that was just created or has just
+/// changed their email address. The resend throttle starts now; the mail
+/// goes out in the background, so a failure is only logged, and the user
+/// can ask for another link.
+pub(crate) async fn send_new_verification_link(state: &SharedAppState, user: &User) {
+    let message = async {
+        User::mark_verification_sent(&state.db_pool, user.id, Duration::ZERO).await?;
+        verification_message(state, user)
+    };
+    match message.await {
+        Ok(message) => mail::send_in_background(state.mailer.clone(), message),
+        Err(err) => tracing::error!(
+            "Failed to send a verification link to user {}: {:?}",
+            user.id,
+            err
+        ),
+    }
+}
+
+/// Marks the email address of the user a signed link was sent to as verified.
+pub async fn verify(
+    State(state): State<SharedAppState>,
+    Query(link): Query<VerificationLink>,
+) -> impl IntoResponse {
+    let invalid = || {
+        response::general_error(
+            StatusCode::FORBIDDEN,
+            "This verification link is invalid or has expired.",
+        )
+    };
+
+    let user = match User::get(&state.db_pool, link.id).await {
+        Ok(user) => user,
+        Err(AppError::DatabaseError(sqlx::Error::RowNotFound)) => return invalid(),
+        Err(err) => return response::error_response(err, "User not found."),
+    };
+    if !link.is_valid(&state.app_key, &user.email) {
+        return invalid();
+    }
+    if user.email_verified_at.is_some() {
+        return response::ok("Your email address is already verified.", ());
+    }
+
+    match User::set_email_verified_at(&state.db_pool, user.id).await {
+        Ok(()) => response::ok("Email verified successfully.", ()),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
+
+/// Sends the caller a new verification link, at most once a minute.
+pub async fn resend(
+    State(state): State<SharedAppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    let Some(user_id) = claims.user_id() else {
+        return response::unexpected_error();
+    };
+    let user = match User::get(&state.db_pool, user_id).await {
+        Ok(user) => user,
+        Err(err) => return response::error_response(err, "User not found."),
+    };
+    if user.email_verified_at.is_some() {
+        return response::ok("Your email address is already verified.", ());
+    }
+
+    match User::mark_verification_sent(&state.db_pool, user.id, RESEND_THROTTLE).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return response::general_error(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Please wait before requesting another verification link.",
+            )
+        }
+        Err(err) => return response::error_response(err, "User not found."),
+    }
+
+    let sent = match verification_message(&state, &user) {
+        Ok(message) => state.mailer.send(message).await,
+        Err(err) => Err(err),
+    };
+    match sent {
+        Ok(()) => response::ok("A new verification link has been sent.", ()),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
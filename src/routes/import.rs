@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::routes::response::{self, ApiResult, FieldErrors};
+
+/// Largest CSV file accepted by the import endpoints.
+pub const MAX_IMPORT_SIZE: usize = 10 * 1024 * 1024;
+
+/// CSV column header to field name.
+pub type ColumnMapping = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Int,
+    Bool,
+}
+
+/// A field an import can fill from a CSV column.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportField {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    /// Some column must be mapped to the field; cells may still be empty.
+    pub required: bool,
+}
+
+impl ImportField {
+    pub const fn text(name: &'static str) -> Self {
+        ImportField {
+            name,
+            kind: FieldKind::Text,
+            required: false,
+        }
+    }
+
+    pub const fn int(name: &'static str) -> Self {
+        ImportField {
+            name,
+            kind: FieldKind::Int,
+            required: false,
+        }
+    }
+
+    pub const fn bool(name: &'static str) -> Self {
+        ImportField {
+            name,
+            kind: FieldKind::Bool,
+            required: false,
+        }
+    }
+
+    pub const fn required(self) -> Self {
+        ImportField {
+            required: true,
+            ..self
+        }
+    }
+
+    /// Converts a non-empty cell to the JSON the API would have been sent.
+    fn value(&self, cell: &str) -> Result<Value, String> {
+        match self.kind {
+            FieldKind::Text => Ok(Value::String(cell.to_string())),
+            FieldKind::Int => cell
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| "Must be a whole number.".to_string()),
+            FieldKind::Bool => match cell.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+                _ => Err("Must be true or false.".to_string()),
+            },
+        }
+    }
+}
+
+/// The query of an import request: `dry_run=true` checks the file without
+/// saving anything, and `map[Column]=field` reads a column into a field.
+/// Columns named after a field are read into it without being mapped.
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    pub mapping: ColumnMapping,
+    pub dry_run: bool,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ImportOptions {
+    type Rejection = ApiResult<()>;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(pairs) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+            .map_err(|err| response::field_error("query", &err.body_text()))?;
+
+        let mut options = ImportOptions::default();
+        for (key, value) in pairs {
+            if key == "dry_run" {
+                options.dry_run = match value.as_str() {
+                    "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => return Err(response::field_error("dry_run", "Must be true or false.")),
+                };
+            } else if let Some(column) = key.strip_prefix("map[").and_then(|k| k.strip_suffix(']'))
+            {
+                options.mapping.insert(column.to_string(), value);
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    /// Line of the file the row starts on; the header is line 1.
+    pub row: u64,
+    pub errors: FieldErrors,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub valid: usize,
+    pub imported: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    pub fn message(&self) -> String {
+        if self.dry_run {
+            format!(
+                "Checked {} rows: {} valid, {} with errors.",
+                self.rows,
+                self.valid,
+                self.errors.len()
+            )
+        } else {
+            format!("Imported {} of {} rows.", self.imported, self.rows)
+        }
+    }
+}
+
+/// A row that parsed cleanly, keyed by field name.
+pub struct ImportRow {
+    pub row: u64,
+    pub values: Map<String, Value>,
+}
+
+/// A CSV file being imported. Rows are checked one by one; a bad row is
+/// reported by line without rejecting the rest of the file.
+pub struct Import {
+    rows: Vec<ImportRow>,
+    errors: Vec<RowError>,
+    /// Field name to the column it was read from, for naming errors.
+    columns: HashMap<&'static str, String>,
+}
+
+impl Import {
+    /// Reads `csv` into `fields` through `mapping`. Problems with the file as
+    /// a whole, such as a mapping naming a missing column, fail the import.
+    pub fn parse(csv: &[u8], mapping: &ColumnMapping, fields: &[ImportField]) -> AppResult<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv);
+        let headers = reader
+            .headers()
+            .map_err(|err| file_error(format!("The file is not valid CSV: {}", err)))?
+            .clone();
+
+        let mut errors = Vec::new();
+        let mut targets: Vec<(usize, ImportField)> = Vec::new();
+        let mut columns = HashMap::new();
+        let mut map = |index: usize, field: ImportField, errors: &mut Vec<String>| {
+            if columns.contains_key(field.name) {
+                errors.push(format!("More than one column is mapped to {}.", field.name));
+                return;
+            }
+            columns.insert(field.name, headers[index].to_string());
+            targets.push((index, field));
+        };
+
+        let mut mapped: Vec<(&String, &String)> = mapping.iter().collect();
+        mapped.sort();
+        for (column, name) in mapped {
+            let Some(field) = fields.iter().find(|field| field.name == name) else {
+                errors.push(format!("Unknown field: {}", name));
+                continue;
+            };
+            match headers.iter().position(|header| header == column) {
+                Some(index) => map(index, *field, &mut errors),
+                None => errors.push(format!("Column not found: {}", column)),
+            }
+        }
+        for (index, header) in headers.iter().enumerate() {
+            if mapping.contains_key(header) {
+                continue;
+            }
+            let field = fields
+                .iter()
+                .find(|field| field.name.eq_ignore_ascii_case(header));
+            if let Some(field) = field.filter(|field| !mapping.values().any(|n| n == field.name)) {
+                map(index, *field, &mut errors);
+            }
+        }
+        for field in fields.iter().filter(|field| field.required) {
+            if !columns.contains_key(field.name) {
+                errors.push(format!("No column is mapped to {}.", field.name));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(AppError::ValidationError(HashMap::from([(
+                "mapping".to_string(),
+                errors,
+            )])));
+        }
+
+        let mut import = Import {
+            rows: Vec::new(),
+            errors: Vec::new(),
+            columns,
+        };
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    let row = err.position().map_or(0, |position| position.line());
+                    import.reject(
+                        row,
+                        general_error(format!("The row is not valid CSV: {}", err)),
+                    );
+                    continue;
+                }
+            };
+            let row = record.position().map_or(0, |position| position.line());
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+
+            let mut values = Map::new();
+            let mut errors = FieldErrors::new();
+            for (index, field) in &targets {
+                let cell = record.get(*index).unwrap_or_default();
+                if cell.is_empty() {
+                    continue;
+                }
+                match field.value(cell) {
+                    Ok(value) => {
+                        values.insert(field.name.to_string(), value);
+                    }
+                    Err(message) => {
+                        errors.insert(field.name.to_string(), vec![message]);
+                    }
+                }
+            }
+            if errors.is_empty() {
+                import.rows.push(ImportRow { row, values });
+            } else {
+                import.reject(row, errors);
+            }
+        }
+
+        if import.rows.is_empty() && import.errors.is_empty() {
+            return Err(file_error("The file has no rows.".to_string()));
+        }
+        Ok(import)
+    }
+
+    /// Runs `check` over every parsed row, keeping the rows it accepts and
+    /// reporting the errors of the rest.
+    pub fn check<T>(
+        &mut self,
+        mut check: impl FnMut(Map<String, Value>) -> Result<T, FieldErrors>,
+    ) -> Vec<(u64, T)> {
+        let mut accepted = Vec::with_capacity(self.rows.len());
+        for ImportRow { row, values } in std::mem::take(&mut self.rows) {
+            match check(values) {
+                Ok(value) => accepted.push((row, value)),
+                Err(errors) => self.reject(row, errors),
+            }
+        }
+        accepted
+    }
+
+    /// Reports `errors` against `row`, naming fields by the column they were
+    /// read from.
+    pub fn reject(&mut self, row: u64, errors: FieldErrors) {
+        let mut named = FieldErrors::new();
+        for (field, messages) in errors {
+            let key = self.columns.get(field.as_str()).cloned().unwrap_or(field);
+            named.entry(key).or_default().extend(messages);
+        }
+        self.errors.push(RowError { row, errors: named });
+    }
+
+    pub fn report(mut self, dry_run: bool, valid: usize, imported: usize) -> ImportReport {
+        self.errors.sort_by_key(|error| error.row);
+        ImportReport {
+            dry_run,
+            rows: valid + self.errors.len(),
+            valid,
+            imported,
+            errors: self.errors,
+        }
+    }
+}
+
+/// Deserializes a row the way the API deserializes a request body and runs
+/// its validation rules.
+pub fn validate_row<T>(values: Map<String, Value>) -> Result<T, FieldErrors>
+where
+    T: serde::de::DeserializeOwned + validator::Validate,
+{
+    let request: T = serde_json::from_value(Value::Object(values))
+        .map_err(|err| response::deserialize_errors(&err))?;
+    request
+        .validate()
+        .map_err(|errors| response::validation_errors(&errors))?;
+    Ok(request)
+}
+
+fn general_error(message: String) -> FieldErrors {
+    HashMap::from([("general".to_string(), vec![message])])
+}
+
+fn file_error(message: String) -> AppError {
+    AppError::ValidationError(HashMap::from([("file".to_string(), vec![message])]))
+}
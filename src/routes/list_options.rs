@@ -0,0 +1,21 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+
+use crate::model::ListOptions;
+use crate::routes::response::{self, ApiResult};
+
+/// Reads [`ListOptions`] from the query string. Bad values are rejected as
+/// field errors keyed by their parameter, like a bad body would be.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ListOptions {
+    type Rejection = ApiResult<()>;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(pairs) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+            .map_err(|err| response::field_error("query", &err.body_text()))?;
+        ListOptions::from_pairs(pairs).map_err(response::validation_failed)
+    }
+}
@@ -1,2 +1,15 @@
+pub mod audit_log;
 pub mod auth;
+pub mod conditional;
+pub mod contacts;
+pub mod customers;
+pub mod email;
+pub mod export;
+pub mod import;
+pub mod list_options;
+pub mod password;
+pub mod permissions;
+pub mod response;
+pub mod roles;
+pub mod search;
 pub mod users;
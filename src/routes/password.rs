@@ -0,0 +1,145 @@
+use axum::extract::rejection::JsonRejection;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::routes::response::{self, ApiResponse};
+use crate::validators::password_rules;
+use crate::{
+    app_state::SharedAppState,
+    auth::security::hash_password_in_background,
+    db,
+    error::AppResult,
+    mail::{
+        self,
+        templates::{self, describe_lifetime, Variables},
+        Message,
+    },
+    model::{
+        password_resets::PasswordReset,
+        patch::Patch,
+        repository::ModelRepository,
+        sessions::Session,
+        users::{User, UserForUpdate},
+    },
+};
+
+pub type ForgotPasswordResponse = ApiResponse<()>;
+pub type ResetPasswordResponse = ApiResponse<()>;
+
+/// Sent whether or not the email belongs to a user, so the endpoint cannot
+/// be used to find out which addresses are registered.
+const FORGOT_PASSWORD_MESSAGE: &str =
+    "If that email address is registered, a password reset link has been sent to it.";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Email must be a valid email address"))]
+    email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, message = "Token is required"))]
+    token: String,
+
+    #[validate(
+        length(
+            min = 3,
+            max = 64,
+            message = "Password must be between 3 and 64 characters"
+        ),
+        custom(function = "password_rules")
+    )]
+    password: String,
+}
+
+pub async fn forgot(
+    State(state): State<SharedAppState>,
+    payload: Result<Json<ForgotPasswordRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let lifetime = state.token_lifetimes.password_reset;
+    let reset = match PasswordReset::issue(&state.db_pool, &payload.email, lifetime).await {
+        Ok(reset) => reset,
+        Err(err) => return response::error_response(err, "User not found."),
+    };
+    if let Some(reset) = reset {
+        // Mailed in the background, so neither a failure nor the time it
+        // takes tells the caller the address is registered
+        match reset_message(&state, &reset) {
+            Ok(message) => mail::send_in_background(state.mailer.clone(), message),
+            Err(err) => tracing::error!("Failed to render password reset mail: {:?}", err),
+        }
+    }
+
+    response::ok(FORGOT_PASSWORD_MESSAGE, ())
+}
+
+fn reset_message(state: &SharedAppState, reset: &PasswordReset) -> AppResult<Message> {
+    let url = format!(
+        "{}/password/reset?token={}",
+        state.app_url.trim_end_matches('/'),
+        reset.token
+    );
+    let variables = Variables::from([
+        ("name", reset.name.clone()),
+        ("url", url),
+        ("token", reset.token.clone()),
+        (
+            "expires_in",
+            describe_lifetime(state.token_lifetimes.password_reset),
+        ),
+    ]);
+    templates::PASSWORD_RESET.render(&reset.email, &variables)
+}
+
+/// Sets a new password with a token from `/password/forgot`. The token is
+/// used up, and the user is signed out of every session.
+pub async fn reset(
+    State(state): State<SharedAppState>,
+    payload: Result<Json<ResetPasswordRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let hashed_password =
+        match hash_password_in_background(payload.password.clone(), state.app_key.clone()).await {
+            Ok(hash) => hash,
+            Err(_) => {
+                return response::general_error(StatusCode::BAD_REQUEST, "Failed to hash password.")
+            }
+        };
+
+    let access_lifetime = state.token_lifetimes.access;
+    let result = db::transaction(&state.db_pool, |tx| {
+        Box::pin(async move {
+            let Some(user_id) = PasswordReset::consume(&mut **tx, &payload.token).await? else {
+                return Ok(false);
+            };
+            let user_for_update = UserForUpdate {
+                password: Patch::Value(hashed_password),
+                ..Default::default()
+            };
+            User::update(&mut **tx, user_id, user_for_update).await?;
+            Session::end_all(&mut **tx, user_id, access_lifetime).await?;
+            Ok(true)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(true) => response::ok("Password reset successfully.", ()),
+        Ok(false) => response::field_error(
+            "token",
+            "This password reset token is invalid or has expired.",
+        ),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
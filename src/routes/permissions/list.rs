@@ -0,0 +1,27 @@
+use crate::app_state::SharedAppState;
+use crate::model::{permissions::Permission, repository::ModelRepository, ListOptions};
+use crate::routes::export::Export;
+use crate::routes::response::{self, ApiResult};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+pub async fn list(
+    State(state): State<SharedAppState>,
+    options: ListOptions,
+    Export(export): Export,
+) -> Result<Response, ApiResult<()>> {
+    let result = match export {
+        Some(export) => {
+            export
+                .respond::<Permission>(&state.db_pool, None, options)
+                .await
+        }
+        None => Permission::list(&state.db_pool, &options)
+            .await
+            .map(|list| Json(list).into_response()),
+    };
+    result.map_err(|err| response::error_response(err, "Permission not found."))
+}
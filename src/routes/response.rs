@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use axum::{extract::rejection::JsonRejection, http::StatusCode, Json};
+use serde::Serialize;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use crate::error::AppError;
+
+/*
+{
+  "success": true,
+  "message": "Customer created successfully.",
+  "data": {
+    "id": 1,
+    "name": "John Doe",
+    "email": "john.doe@example.com",
+    "created_at": "2024-12-17T10:00:00Z",
+    "updated_at": "2024-12-17T10:00:00Z"
+  }
+}
+
+
+{
+  "success": false,
+  "message": "Validation failed.",
+  "errors": {
+    "name": [
+      "The name field is required."
+    ]
+  }
+}
+
+{
+  "success": false,
+  "message": "An unexpected error occurred. Please try again later."
+}
+
+ */
+
+pub type FieldErrors = HashMap<String, Vec<String>>;
+
+pub type ApiResult<T> = (StatusCode, Json<ApiResponse<T>>);
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ApiResponse<T> {
+    Success(SuccessResponse<T>),
+    ValidationError(ValidationErrorResponse),
+    GeneralError(GeneralErrorResponse),
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuccessResponse<T> {
+    pub success: bool,
+    pub message: String,
+    pub data: T,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationErrorResponse {
+    pub success: bool,
+    pub message: String,
+    pub errors: FieldErrors,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeneralErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn success(message: &str, data: T) -> Self {
+        ApiResponse::Success(SuccessResponse {
+            success: true,
+            message: message.to_string(),
+            data,
+        })
+    }
+
+    pub fn validation_error(message: &str, errors: FieldErrors) -> Self {
+        ApiResponse::ValidationError(ValidationErrorResponse {
+            success: false,
+            message: message.to_string(),
+            errors,
+        })
+    }
+
+    pub fn general_error(message: &str) -> Self {
+        ApiResponse::GeneralError(GeneralErrorResponse {
+            success: false,
+            message: message.to_string(),
+        })
+    }
+}
+
+pub fn ok<T>(message: &str, data: T) -> ApiResult<T> {
+    (StatusCode::OK, Json(ApiResponse::success(message, data)))
+}
+
+pub fn created<T>(message: &str, data: T) -> ApiResult<T> {
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(message, data)),
+    )
+}
+
+pub fn validation_failed<T>(errors: FieldErrors) -> ApiResult<T> {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ApiResponse::validation_error("Validation failed.", errors)),
+    )
+}
+
+pub fn field_error<T>(field: &str, message: &str) -> ApiResult<T> {
+    validation_failed(HashMap::from([(
+        field.to_string(),
+        vec![message.to_string()],
+    )]))
+}
+
+pub fn general_error<T>(status: StatusCode, message: &str) -> ApiResult<T> {
+    (status, Json(ApiResponse::general_error(message)))
+}
+
+pub fn unexpected_error<T>() -> ApiResult<T> {
+    general_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "An unexpected error occurred. Please try again later.",
+    )
+}
+
+/// Maps a repository error onto the envelope, treating a missing row as a 404,
+/// rejected input as a 422, a missing permission as a 403 and a stale
+/// `If-Match` as a 412.
+pub fn error_response<T>(err: AppError, not_found: &str) -> ApiResult<T> {
+    match err {
+        AppError::DatabaseError(sqlx::Error::RowNotFound) => {
+            general_error(StatusCode::NOT_FOUND, not_found)
+        }
+        AppError::ValidationError(errors) => validation_failed(errors),
+        AppError::Forbidden => general_error(
+            StatusCode::FORBIDDEN,
+            "You do not have permission to perform this action.",
+        ),
+        AppError::PreconditionFailed => general_error(
+            StatusCode::PRECONDITION_FAILED,
+            "The record has been changed since it was fetched.",
+        ),
+        err => {
+            tracing::error!("Database error: {:?}", err);
+            unexpected_error()
+        }
+    }
+}
+
+/// Unwraps and validates a JSON payload, producing the validation envelope on failure.
+pub fn validate_payload<P, T>(payload: Result<Json<P>, JsonRejection>) -> Result<P, ApiResult<T>>
+where
+    P: Validate,
+{
+    // Step 1: Handle deserialization errors
+    let payload = match payload {
+        Ok(json) => json.0,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::validation_error(
+                    "Invalid input.",
+                    rejection_errors(&err),
+                )),
+            ))
+        }
+    };
+
+    // Step 2. Validate the payload
+    if let Err(errors) = payload.validate() {
+        return Err(validation_failed(validation_errors(&errors)));
+    }
+
+    Ok(payload)
+}
+
+/// Flattens validator errors into the envelope's field map. Errors from
+/// nested structs and lists are keyed by path, e.g. `contacts[0].email`.
+pub fn validation_errors(validation_errors: &ValidationErrors) -> FieldErrors {
+    let mut errors = FieldErrors::new();
+    collect_validation_errors(validation_errors, None, &mut errors);
+    errors
+}
+
+fn collect_validation_errors(
+    validation_errors: &ValidationErrors,
+    prefix: Option<&str>,
+    errors: &mut FieldErrors,
+) {
+    for (field, kind) in validation_errors.errors() {
+        let key = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                errors
+                    .entry(key)
+                    .or_default()
+                    .extend(field_errors.iter().map(|e| {
+                        e.message
+                            .clone()
+                            .unwrap_or_else(|| e.code.clone())
+                            .to_string()
+                    }))
+            }
+            ValidationErrorsKind::Struct(nested) => {
+                collect_validation_errors(nested, Some(&key), errors)
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    let key = format!("{}[{}]", key, index);
+                    collect_validation_errors(nested, Some(&key), errors);
+                }
+            }
+        }
+    }
+}
+
+pub fn rejection_errors(err: &JsonRejection) -> FieldErrors {
+    let mut errors = HashMap::new();
+
+    // Attempt to extract the specific field name causing the error
+    let field = match err {
+        JsonRejection::JsonDataError(data_err) => extract_missing_field(&data_err.body_text()),
+        _ => None,
+    };
+
+    match field {
+        Some(field) => {
+            errors.insert(field, vec!["This field is required.".to_string()]);
+        }
+        None => {
+            errors.insert(
+                "general".to_string(),
+                vec![format!("Invalid input: {}", err)],
+            );
+        }
+    }
+
+    errors
+}
+
+/// Like [`rejection_errors`], for a value deserialized outside the extractor.
+pub fn deserialize_errors(err: &serde_json::Error) -> FieldErrors {
+    let message = err.to_string();
+    match extract_missing_field(&message) {
+        Some(field) => HashMap::from([(field, vec!["This field is required.".to_string()])]),
+        None => HashMap::from([(
+            "general".to_string(),
+            vec![format!("Invalid input: {}", message)],
+        )]),
+    }
+}
+
+fn extract_missing_field(error_message: &str) -> Option<String> {
+    let missing_field_prefix = "missing field `";
+
+    if let Some(start) = error_message.find(missing_field_prefix) {
+        let start_index = start + missing_field_prefix.len();
+        if let Some(end_index) = error_message[start_index..].find('`') {
+            return Some(error_message[start_index..start_index + end_index].to_string());
+        }
+    }
+
+    None
+}
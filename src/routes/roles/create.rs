@@ -0,0 +1,52 @@
+use crate::error::AppError;
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::{
+    app_state::SharedAppState,
+    model::{
+        repository::ModelRepository,
+        roles::{Role, RoleForCreate},
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::Deserialize;
+use validator::Validate;
+
+pub type CreateRoleResponse = ApiResponse<Role>;
+
+/// Unique constraint on `roles.name`.
+const UNIQUE_NAME_CONSTRAINT: &str = "roles_name_key";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateRoleRequest {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Name must be between 1 and 64 characters"
+    ))]
+    name: String,
+}
+
+/// Reports a duplicate role name as a field error rather than a 500.
+pub(crate) fn role_error_response<T>(err: AppError) -> ApiResult<T> {
+    if err.constraint() == Some(UNIQUE_NAME_CONSTRAINT) {
+        return response::field_error("name", "A role with this name already exists.");
+    }
+
+    response::error_response(err, "Role not found.")
+}
+
+pub async fn create(
+    State(state): State<SharedAppState>,
+    payload: Result<Json<CreateRoleRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    match Role::create(&state.db_pool, RoleForCreate { name: payload.name }).await {
+        Ok(role) => response::created("Role created successfully.", role),
+        Err(err) => role_error_response(err),
+    }
+}
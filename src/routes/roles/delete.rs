@@ -0,0 +1,19 @@
+use crate::routes::response::{self, ApiResponse};
+use crate::{app_state::SharedAppState, model::roles::Role};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+pub type DeleteRoleResponse = ApiResponse<()>;
+
+pub async fn delete(State(state): State<SharedAppState>, Path(id): Path<i32>) -> impl IntoResponse {
+    match Role::delete_unless_admin(&state.db_pool, id).await {
+        Ok(true) => response::ok("Role deleted successfully.", ()),
+        Ok(false) => {
+            response::general_error(StatusCode::CONFLICT, "The admin role cannot be deleted.")
+        }
+        Err(err) => response::error_response(err, "Role not found."),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::app_state::SharedAppState;
+use crate::middleware::EffectivePermissions;
+use crate::model::{
+    fields::{Fields, Sparse},
+    include::{self, Include, WithRelations},
+    repository::ModelRepository,
+    roles::Role,
+};
+use crate::routes::conditional::IfNoneMatch;
+use crate::routes::response;
+use axum::http::StatusCode;
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub enum GetRoleResponse {
+    Success(Sparse<WithRelations<Role>>),
+    Error { error: String },
+}
+
+pub async fn get(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    Query(include): Query<Include>,
+    Query(fields): Query<Fields>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    if_none_match: IfNoneMatch,
+) -> Response {
+    let relations = match include.relations::<Role>(|permission| permissions.contains(permission)) {
+        Ok(relations) => relations,
+        Err(err) => return response::error_response::<()>(err, "Role not found.").into_response(),
+    };
+
+    let fields = match fields.field_set(Role::FIELDS) {
+        Ok(fields) => fields,
+        Err(err) => return response::error_response::<()>(err, "Role not found.").into_response(),
+    };
+
+    match Role::get_sparse(&state.db_pool, id, fields.as_ref()).await {
+        Ok(data) => {
+            // Included rows and sparse fieldsets change the representation
+            // without changing this row's version
+            let etag = (relations.is_empty() && fields.is_none())
+                .then(|| data.etag())
+                .flatten();
+            match include::load_one(&state.db_pool, data, &relations).await {
+                Ok(data) => if_none_match.respond(
+                    etag,
+                    (
+                        StatusCode::OK,
+                        Json(GetRoleResponse::Success(Sparse { row: data, fields })),
+                    ),
+                ),
+                Err(err) => response::error_response::<()>(err, "Role not found.").into_response(),
+            }
+        }
+        Err(err) => response::error_response::<()>(err, "Role not found.").into_response(),
+    }
+}
@@ -0,0 +1,34 @@
+use crate::app_state::SharedAppState;
+use crate::middleware::EffectivePermissions;
+use crate::model::{include::Include, repository::ModelRepository, roles::Role, ListOptions};
+use crate::routes::export::Export;
+use crate::routes::response::{self, ApiResult};
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+pub async fn list(
+    State(state): State<SharedAppState>,
+    options: ListOptions,
+    Query(include): Query<Include>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    Export(export): Export,
+) -> Result<Response, ApiResult<()>> {
+    let relations = include
+        .relations::<Role>(|permission| permissions.contains(permission))
+        .map_err(|err| response::error_response(err, "Role not found."))?;
+
+    let result = match export {
+        Some(export) => export.respond::<Role>(&state.db_pool, None, options).await,
+        None => match Role::list(&state.db_pool, &options).await {
+            Ok(list) => list
+                .include(&state.db_pool, &relations)
+                .await
+                .map(|list| Json(list).into_response()),
+            Err(err) => Err(err),
+        },
+    };
+    result.map_err(|err| response::error_response(err, "Role not found."))
+}
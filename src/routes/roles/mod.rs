@@ -0,0 +1,13 @@
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod list;
+pub mod permissions;
+pub mod update;
+
+pub use create::create;
+pub use delete::delete;
+pub use get::get;
+pub use list::list;
+pub use permissions::{attach_permission, detach_permission, list_permissions};
+pub use update::{patch, update};
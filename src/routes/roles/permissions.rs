@@ -0,0 +1,112 @@
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::{
+    app_state::SharedAppState,
+    error::AppResult,
+    middleware::EffectivePermissions,
+    model::{permissions::Permission, repository::ModelRepository, roles::Role},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+pub type RolePermissionsResponse = ApiResponse<RolePermissions>;
+
+#[derive(Debug, Serialize)]
+pub struct RolePermissions {
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+}
+
+async fn role_permissions(pool: &PgPool, role: Role) -> AppResult<RolePermissions> {
+    let permissions = Permission::for_role(pool, role.id).await?;
+    Ok(RolePermissions { role, permissions })
+}
+
+/// Loads the role and permission named in the path, or the 404 to return.
+async fn find_pair(
+    pool: &PgPool,
+    role_id: i32,
+    permission_id: i32,
+) -> Result<(Role, Permission), ApiResult<RolePermissions>> {
+    let role = Role::get(pool, role_id)
+        .await
+        .map_err(|err| response::error_response(err, "Role not found."))?;
+    let permission = Permission::get(pool, permission_id)
+        .await
+        .map_err(|err| response::error_response(err, "Permission not found."))?;
+    Ok((role, permission))
+}
+
+pub async fn list_permissions(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    let role = match Role::get(&state.db_pool, id).await {
+        Ok(role) => role,
+        Err(err) => return response::error_response(err, "Role not found."),
+    };
+
+    match role_permissions(&state.db_pool, role).await {
+        Ok(data) => response::ok("Role permissions retrieved successfully.", data),
+        Err(err) => response::error_response(err, "Role not found."),
+    }
+}
+
+pub async fn attach_permission(
+    State(state): State<SharedAppState>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    Path((id, permission_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    let (role, permission) = match find_pair(&state.db_pool, id, permission_id).await {
+        Ok(pair) => pair,
+        Err(response) => return response,
+    };
+
+    // Otherwise `roles.update` alone would be enough to grant anything
+    if !permissions.0.contains(&permission.name) {
+        return response::general_error(
+            StatusCode::FORBIDDEN,
+            "You cannot grant a permission you do not hold.",
+        );
+    }
+
+    if let Err(err) = Role::grant_permission(&state.db_pool, role.id, permission_id).await {
+        return response::error_response(err, "Role not found.");
+    }
+
+    match role_permissions(&state.db_pool, role).await {
+        Ok(data) => response::ok("Permission attached successfully.", data),
+        Err(err) => response::error_response(err, "Role not found."),
+    }
+}
+
+pub async fn detach_permission(
+    State(state): State<SharedAppState>,
+    Path((id, permission_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    let (role, _) = match find_pair(&state.db_pool, id, permission_id).await {
+        Ok(pair) => pair,
+        Err(response) => return response,
+    };
+
+    match Role::revoke_permission_unless_admin(&state.db_pool, role.id, permission_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return response::general_error(
+                StatusCode::CONFLICT,
+                "Permissions cannot be detached from the admin role.",
+            )
+        }
+        Err(err) => return response::error_response(err, "Role not found."),
+    }
+
+    match role_permissions(&state.db_pool, role).await {
+        Ok(data) => response::ok("Permission detached successfully.", data),
+        Err(err) => response::error_response(err, "Role not found."),
+    }
+}
@@ -0,0 +1,99 @@
+use crate::routes::conditional::IfMatch;
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::{
+    app_state::SharedAppState,
+    error::AppResult,
+    model::{
+        patch::{not_null, Patch},
+        roles::{Role, RoleForUpdate},
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use super::create::role_error_response;
+
+pub type UpdateRoleResponse = ApiResponse<Role>;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRoleRequest {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Name must be between 1 and 64 characters"
+    ))]
+    name: String,
+}
+
+pub async fn update(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<UpdateRoleRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let role_for_update = RoleForUpdate {
+        name: Patch::Value(payload.name),
+    };
+
+    respond(
+        Role::update_unless_admin(&state.db_pool, id, role_for_update, if_match.condition()).await,
+    )
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchRoleRequest {
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Name cannot be null"),
+        length(
+            min = 1,
+            max = 64,
+            message = "Name must be between 1 and 64 characters"
+        )
+    )]
+    name: Patch<String>,
+}
+
+pub async fn patch(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<PatchRoleRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    respond(
+        Role::update_unless_admin(
+            &state.db_pool,
+            id,
+            RoleForUpdate { name: payload.name },
+            if_match.condition(),
+        )
+        .await,
+    )
+}
+
+fn respond(result: AppResult<Option<Role>>) -> ApiResult<Role> {
+    match result {
+        Ok(Some(role)) => response::ok("Role updated successfully.", role),
+        Ok(None) => {
+            response::general_error(StatusCode::CONFLICT, "The admin role cannot be renamed.")
+        }
+        Err(err) => role_error_response(err),
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    app_state::SharedAppState,
+    auth::claims::Claims,
+    error::AppResult,
+    middleware::EffectivePermissions,
+    model::{
+        contacts::Contact, customers::Customer, permission_catalogue::PermissionName,
+        permissions::Permission, query::SearchMode, repository::ModelRepository, users::User,
+        ListOptions,
+    },
+    routes::response,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+const DEFAULT_LIMIT: u16 = 5;
+const MAX_LIMIT: u16 = 25;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    q: Option<String>,
+    /// Results per entity type.
+    limit: Option<u16>,
+}
+
+/// Matches grouped by entity type. Types the caller may not view are left out.
+#[derive(Serialize)]
+pub struct SearchResults {
+    query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    users: Option<Vec<SearchHit<User>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    customers: Option<Vec<SearchHit<Customer>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contacts: Option<Vec<SearchHit<Contact>>>,
+}
+
+#[derive(Serialize)]
+pub struct SearchHit<T> {
+    /// The first search column that matched, e.g. `email`.
+    matched_field: Option<String>,
+    /// That column with its matches wrapped in `<mark>` tags.
+    highlight: Option<String>,
+    data: T,
+}
+
+/// `GET /search?q=`: ranked full-text search across users, customers and
+/// contacts, capped at `limit` results per type.
+pub async fn search(
+    State(state): State<SharedAppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<SearchQuery>,
+) -> Response {
+    let Some(q) = query
+        .q
+        .map(|q| q.trim().to_string())
+        .filter(|q| !q.is_empty())
+    else {
+        return response::field_error::<()>("q", "The search term is required.").into_response();
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let Some(user_id) = claims.user_id() else {
+        return response::general_error::<()>(StatusCode::UNAUTHORIZED, "Unauthorized.")
+            .into_response();
+    };
+    let permissions = match Permission::names_for_user(&state.db_pool, user_id).await {
+        Ok(names) => EffectivePermissions(names.into_iter().collect()),
+        Err(err) => return response::error_response::<()>(err, "User not found.").into_response(),
+    };
+
+    let pool = &state.db_pool;
+    let results = tokio::try_join!(
+        hits::<User>(pool, &permissions, PermissionName::UsersView, &q, limit),
+        hits::<Customer>(pool, &permissions, PermissionName::CustomersView, &q, limit),
+        hits::<Contact>(pool, &permissions, PermissionName::ContactsView, &q, limit),
+    );
+
+    match results {
+        Ok((users, customers, contacts)) => Json(SearchResults {
+            query: q,
+            users,
+            customers,
+            contacts,
+        })
+        .into_response(),
+        Err(err) => response::error_response::<()>(err, "Not found.").into_response(),
+    }
+}
+
+/// The best matches of one model, or `None` without `permission`.
+async fn hits<M: ModelRepository>(
+    pool: &PgPool,
+    permissions: &EffectivePermissions,
+    permission: PermissionName,
+    q: &str,
+    limit: u16,
+) -> AppResult<Option<Vec<SearchHit<M>>>> {
+    if !permissions.contains(permission) {
+        return Ok(None);
+    }
+
+    let options = ListOptions {
+        q: Some(q.to_string()),
+        per_page: Some(limit),
+        count: Some(false),
+        search: Some(SearchMode::FullText),
+        ..Default::default()
+    };
+    let list = M::list(pool, &options).await?;
+    // Ranked lists carry one highlight map per row
+    let highlights = list.highlights.unwrap_or_default();
+
+    let hits = list
+        .data
+        .into_iter()
+        .zip(highlights)
+        .take(limit as usize)
+        .map(|(data, mut highlights)| {
+            let matched_field = M::SEARCH_COLUMNS
+                .iter()
+                .find(|column| highlights.contains_key(**column))
+                .map(|column| column.to_string());
+            let highlight = matched_field
+                .as_ref()
+                .and_then(|column| highlights.remove(column));
+            SearchHit {
+                matched_field,
+                highlight,
+                data,
+            }
+        })
+        .collect();
+    Ok(Some(hits))
+}
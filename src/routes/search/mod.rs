@@ -0,0 +1,3 @@
+pub mod global;
+
+pub use global::search;
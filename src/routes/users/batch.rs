@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::routes::email::send_new_verification_link;
+use crate::routes::response::{self, ApiResponse, FieldErrors};
+use crate::{
+    app_state::SharedAppState,
+    auth::security::hash_passwords,
+    middleware::EffectivePermissions,
+    model::{
+        permission_catalogue::PermissionName,
+        repository::ModelRepository,
+        users::{User, UserForCreate},
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::create::{user_error_response, CreateUserRequest};
+
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Body of `POST /users/batch`. Items are checked one by one so that a bad
+/// item is reported without rejecting the rest of the batch.
+#[derive(Debug, Deserialize)]
+pub struct BatchUsersRequest {
+    users: Vec<serde_json::Value>,
+
+    /// Update the name of users whose email already exists instead of
+    /// failing them. Their password is left as it is.
+    #[serde(default)]
+    upsert: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<FieldErrors>,
+}
+
+pub type BatchUsersResponse = ApiResponse<Vec<BatchItemResult>>;
+
+pub async fn batch(
+    State(state): State<SharedAppState>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    payload: Result<Json<BatchUsersRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(payload)) => payload,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::validation_error(
+                    "Invalid input.",
+                    response::rejection_errors(&err),
+                )),
+            )
+        }
+    };
+
+    if payload.users.is_empty() || payload.users.len() > MAX_BATCH_SIZE {
+        return response::field_error(
+            "users",
+            &format!(
+                "Between 1 and {} users can be sent per batch.",
+                MAX_BATCH_SIZE
+            ),
+        );
+    }
+
+    if payload.upsert && !permissions.contains(PermissionName::UsersUpdate) {
+        return response::general_error(
+            StatusCode::FORBIDDEN,
+            "You do not have permission to perform this action.",
+        );
+    }
+
+    let mut failures: HashMap<usize, FieldErrors> = HashMap::new();
+    let mut requests = Vec::new();
+    for (index, value) in payload.users.into_iter().enumerate() {
+        match serde_json::from_value::<CreateUserRequest>(value) {
+            Ok(request) => match request.validate() {
+                Ok(()) => requests.push((index, request)),
+                Err(errors) => {
+                    failures.insert(index, response::validation_errors(&errors));
+                }
+            },
+            Err(err) => {
+                failures.insert(index, response::deserialize_errors(&err));
+            }
+        }
+    }
+
+    // The same email twice would make a multi-row insert fail as a whole
+    let mut seen = HashSet::new();
+    requests.retain(|(index, request)| {
+        if seen.insert(request.email.clone()) {
+            return true;
+        }
+        failures.insert(
+            *index,
+            email_error("This email appears more than once in the batch."),
+        );
+        false
+    });
+
+    if !payload.upsert {
+        let emails: Vec<String> = requests.iter().map(|(_, r)| r.email.clone()).collect();
+        let existing = match User::existing_emails(&state.db_pool, &emails).await {
+            Ok(existing) => existing,
+            Err(err) => return response::error_response(err, "User not found."),
+        };
+        requests.retain(|(index, request)| {
+            if !existing.contains(&request.email) {
+                return true;
+            }
+            failures.insert(
+                *index,
+                email_error("A user with this email already exists."),
+            );
+            false
+        });
+    }
+
+    let passwords = requests
+        .iter()
+        .map(|(_, request)| request.password.clone())
+        .collect();
+    let hashed = match hash_passwords(passwords, state.app_key.clone()).await {
+        Ok(hashed) => hashed,
+        Err(_) => {
+            return response::general_error(StatusCode::BAD_REQUEST, "Failed to hash password.")
+        }
+    };
+
+    let mut indexes = HashMap::new();
+    let mut rows = Vec::with_capacity(hashed.len());
+    for ((index, request), password) in requests.into_iter().zip(hashed) {
+        indexes.insert(request.email.clone(), index);
+        rows.push(UserForCreate {
+            name: request.name,
+            email: request.email,
+            password,
+        });
+    }
+
+    let result = if payload.upsert {
+        User::upsert(&state.db_pool, rows, "email", &["name"]).await
+    } else {
+        User::create_many(&state.db_pool, rows).await
+    };
+    let users = match result {
+        Ok(users) => users,
+        Err(err) => return user_error_response(err),
+    };
+
+    for user in users.iter().filter(|user| user.email_verified_at.is_none()) {
+        send_new_verification_link(&state, user).await;
+    }
+
+    let created = users.len();
+    let mut results: Vec<BatchItemResult> = users
+        .into_iter()
+        .filter_map(|user| {
+            let index = *indexes.get(&user.email)?;
+            Some(BatchItemResult {
+                index,
+                success: true,
+                data: Some(user),
+                errors: None,
+            })
+        })
+        .collect();
+    results.extend(failures.into_iter().map(|(index, errors)| BatchItemResult {
+        index,
+        success: false,
+        data: None,
+        errors: Some(errors),
+    }));
+    results.sort_by_key(|result| result.index);
+
+    let message = format!("Saved {} of {} users.", created, results.len());
+    response::ok(&message, results)
+}
+
+fn email_error(message: &str) -> FieldErrors {
+    HashMap::from([("email".to_string(), vec![message.to_string()])])
+}
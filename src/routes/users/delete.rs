@@ -0,0 +1,32 @@
+use crate::routes::response::{self, ApiResponse};
+use crate::{app_state::SharedAppState, auth::claims::Claims, model::users::User};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+
+pub type DeleteUserResponse = ApiResponse<()>;
+
+pub async fn delete(
+    State(state): State<SharedAppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if claims.user_id() == Some(id) {
+        return response::general_error(
+            StatusCode::CONFLICT,
+            "You cannot delete your own account.",
+        );
+    }
+
+    match User::delete_unless_last_admin(&state.db_pool, id).await {
+        Ok(true) => response::ok("User deleted successfully.", ()),
+        Ok(false) => response::general_error(
+            StatusCode::CONFLICT,
+            "The last administrator cannot be deleted.",
+        ),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
@@ -1,25 +1,34 @@
 use crate::app_state::SharedAppState;
-use crate::model::{repository::ModelRepository, users::User, List, ListOptions, Paginator};
+use crate::middleware::EffectivePermissions;
+use crate::model::{include::Include, repository::ModelRepository, users::User, ListOptions};
+use crate::routes::export::Export;
+use crate::routes::response::{self, ApiResult};
 use axum::{
     extract::{Query, State},
-    response::IntoResponse,
-    Json,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 
 pub async fn list(
     State(state): State<SharedAppState>,
-    Query(options): Query<ListOptions>,
-) -> impl IntoResponse {
-    match User::list(&state.db_pool, &options).await {
-        Ok(data) => Json(data),
-        Err(_) => Json(List {
-            data: vec![],
-            pagination: Paginator {
-                current_page: options.page.unwrap_or(1),
-                per_page: options.per_page.unwrap_or(1),
-                total_pages: 0,
-                total_count: 0,
-            },
-        }),
-    }
+    options: ListOptions,
+    Query(include): Query<Include>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    Export(export): Export,
+) -> Result<Response, ApiResult<()>> {
+    let relations = include
+        .relations::<User>(|permission| permissions.contains(permission))
+        .map_err(|err| response::error_response(err, "User not found."))?;
+
+    let result = match export {
+        Some(export) => export.respond::<User>(&state.db_pool, None, options).await,
+        None => match User::list(&state.db_pool, &options).await {
+            Ok(list) => list
+                .include(&state.db_pool, &relations)
+                .await
+                .map(|list| Json(list).into_response()),
+            Err(err) => Err(err),
+        },
+    };
+    result.map_err(|err| response::error_response(err, "User not found."))
 }
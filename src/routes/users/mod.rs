@@ -1,11 +1,15 @@
+pub mod batch;
 pub mod create;
-//pub mod delete;
+pub mod delete;
 pub mod get;
 pub mod list;
-//pub mod update;
+pub mod roles;
+pub mod update;
 
+pub use batch::batch;
 pub use create::create;
-//pub use delete::delete;
+pub use delete::delete;
 pub use get::get;
 pub use list::list;
-//pub use update::update;
+pub use roles::{assign_role, list_roles, revoke_role};
+pub use update::{patch, update};
@@ -0,0 +1,120 @@
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::{
+    app_state::SharedAppState,
+    error::AppResult,
+    middleware::EffectivePermissions,
+    model::{permissions::Permission, repository::ModelRepository, roles::Role, users::User},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+pub type UserPermissionsResponse = ApiResponse<UserPermissions>;
+
+#[derive(Debug, Serialize)]
+pub struct UserPermissions {
+    pub user_id: i32,
+    pub roles: Vec<Role>,
+    pub permissions: Vec<String>,
+}
+
+async fn user_permissions(pool: &PgPool, user_id: i32) -> AppResult<UserPermissions> {
+    Ok(UserPermissions {
+        user_id,
+        roles: Role::for_user(pool, user_id).await?,
+        permissions: Permission::names_for_user(pool, user_id).await?,
+    })
+}
+
+/// Checks the user and role named in the path exist, or returns the 404.
+async fn find_pair(
+    pool: &PgPool,
+    user_id: i32,
+    role_id: i32,
+) -> Result<(), ApiResult<UserPermissions>> {
+    User::get(pool, user_id)
+        .await
+        .map_err(|err| response::error_response(err, "User not found."))?;
+    Role::get(pool, role_id)
+        .await
+        .map_err(|err| response::error_response(err, "Role not found."))?;
+    Ok(())
+}
+
+pub async fn list_roles(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(err) = User::get(&state.db_pool, id).await {
+        return response::error_response(err, "User not found.");
+    }
+
+    match user_permissions(&state.db_pool, id).await {
+        Ok(data) => response::ok("User roles retrieved successfully.", data),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
+
+pub async fn assign_role(
+    State(state): State<SharedAppState>,
+    Extension(permissions): Extension<EffectivePermissions>,
+    Path((id, role_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    if let Err(response) = find_pair(&state.db_pool, id, role_id).await {
+        return response;
+    }
+
+    // Otherwise `roles.assign` alone would be enough to become an admin
+    let granted = match Permission::for_role(&state.db_pool, role_id).await {
+        Ok(granted) => granted,
+        Err(err) => return response::error_response(err, "Role not found."),
+    };
+    if !granted
+        .iter()
+        .all(|permission| permissions.0.contains(&permission.name))
+    {
+        return response::general_error(
+            StatusCode::FORBIDDEN,
+            "You cannot assign a role with permissions you do not hold.",
+        );
+    }
+
+    if let Err(err) = Role::assign_to_user(&state.db_pool, role_id, id).await {
+        return response::error_response(err, "User not found.");
+    }
+
+    match user_permissions(&state.db_pool, id).await {
+        Ok(data) => response::ok("Role assigned successfully.", data),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
+
+pub async fn revoke_role(
+    State(state): State<SharedAppState>,
+    Path((id, role_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    if let Err(response) = find_pair(&state.db_pool, id, role_id).await {
+        return response;
+    }
+
+    match Role::remove_from_user_unless_last_admin(&state.db_pool, role_id, id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return response::general_error(
+                StatusCode::CONFLICT,
+                "The last administrator cannot lose the admin role.",
+            )
+        }
+        Err(err) => return response::error_response(err, "User not found."),
+    }
+
+    match user_permissions(&state.db_pool, id).await {
+        Ok(data) => response::ok("Role revoked successfully.", data),
+        Err(err) => response::error_response(err, "User not found."),
+    }
+}
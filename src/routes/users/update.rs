@@ -0,0 +1,207 @@
+use crate::routes::conditional::IfMatch;
+use crate::routes::email::send_new_verification_link;
+use crate::routes::response::{self, ApiResponse, ApiResult};
+use crate::validators::password_rules;
+use crate::{
+    app_state::SharedAppState,
+    auth::security::hash_password_in_background,
+    db,
+    model::{
+        patch::{not_null, Patch},
+        repository::ModelRepository,
+        users::{User, UserForUpdate},
+    },
+};
+use axum::extract::rejection::JsonRejection;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+use super::create::user_error_response;
+
+pub type UpdateUserResponse = ApiResponse<User>;
+
+/// Body of `PUT /users/:id`. The password is only changed when one is given.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateUserRequest {
+    #[validate(length(
+        min = 3,
+        max = 80,
+        message = "Name must be between 3 and 80 characters"
+    ))]
+    name: String,
+
+    #[validate(email(message = "Email must be a valid email address"))]
+    email: String,
+
+    #[validate(
+        length(
+            min = 3,
+            max = 64,
+            message = "Password must be between 3 and 64 characters"
+        ),
+        custom(function = "password_rules")
+    )]
+    password: Option<String>,
+
+    customer_id: Option<i32>,
+}
+
+/// Body of `PATCH /users/:id`: only the fields sent are changed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchUserRequest {
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Name cannot be null"),
+        length(
+            min = 3,
+            max = 80,
+            message = "Name must be between 3 and 80 characters"
+        )
+    )]
+    name: Patch<String>,
+
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Email cannot be null"),
+        email(message = "Email must be a valid email address")
+    )]
+    email: Patch<String>,
+
+    #[serde(default)]
+    #[validate(
+        custom(function = "not_null", message = "Password cannot be null"),
+        length(
+            min = 3,
+            max = 64,
+            message = "Password must be between 3 and 64 characters"
+        ),
+        custom(function = "patched_password_rules")
+    )]
+    password: Patch<String>,
+
+    #[serde(default)]
+    customer_id: Patch<i32>,
+}
+
+fn patched_password_rules(password: &Patch<String>) -> Result<(), ValidationError> {
+    match password.value() {
+        Some(password) => password_rules(password),
+        None => Ok(()),
+    }
+}
+
+pub async fn update(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<UpdateUserRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let password = payload.password.map_or(Patch::Absent, Patch::Value);
+    let hashed_password = match hash_new_password(&state, password).await {
+        Ok(hashed_password) => hashed_password,
+        Err(response) => return response,
+    };
+
+    let user_for_update = UserForUpdate {
+        name: Patch::Value(payload.name),
+        email: Patch::Value(payload.email),
+        password: hashed_password,
+        customer_id: payload.customer_id.into(),
+        ..Default::default()
+    };
+
+    save(&state, id, user_for_update, if_match).await
+}
+
+pub async fn patch(
+    State(state): State<SharedAppState>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    payload: Result<Json<PatchUserRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match response::validate_payload(payload) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let hashed_password = match hash_new_password(&state, payload.password).await {
+        Ok(hashed_password) => hashed_password,
+        Err(response) => return response,
+    };
+
+    let user_for_update = UserForUpdate {
+        name: payload.name,
+        email: payload.email,
+        password: hashed_password,
+        customer_id: payload.customer_id,
+        ..Default::default()
+    };
+
+    save(&state, id, user_for_update, if_match).await
+}
+
+/// Saves the changes. A new email address has to be verified again: its
+/// verification is cleared in the same update, and a new link is sent.
+async fn save(
+    state: &SharedAppState,
+    id: i32,
+    mut user_for_update: UserForUpdate,
+    if_match: IfMatch,
+) -> ApiResult<User> {
+    let result = db::transaction(&state.db_pool, |tx| {
+        Box::pin(async move {
+            let email_changed = match user_for_update.email.value() {
+                Some(email) => User::email_for_update(&mut **tx, id)
+                    .await?
+                    .is_some_and(|current| current != *email),
+                None => false,
+            };
+            if email_changed {
+                user_for_update.email_verified_at = Patch::Null;
+            }
+            let user =
+                User::update_if_match(&mut **tx, id, user_for_update, if_match.condition()).await?;
+            Ok((user, email_changed))
+        })
+    })
+    .await;
+
+    match result {
+        Ok((user, email_changed)) => {
+            if email_changed {
+                send_new_verification_link(state, &user).await;
+            }
+            response::ok("User updated successfully.", user)
+        }
+        Err(err) => user_error_response(err),
+    }
+}
+
+/// Hashes a newly supplied password; anything else leaves the password alone.
+async fn hash_new_password<T>(
+    state: &SharedAppState,
+    password: Patch<String>,
+) -> Result<Patch<String>, ApiResult<T>> {
+    let Patch::Value(password) = password else {
+        return Ok(Patch::Absent);
+    };
+
+    match hash_password_in_background(password, state.app_key.clone()).await {
+        Ok(hash) => Ok(Patch::Value(hash)),
+        Err(_) => Err(response::general_error(
+            StatusCode::BAD_REQUEST,
+            "Failed to hash password.",
+        )),
+    }
+}
@@ -1,11 +1,18 @@
 use std::{env, sync::Arc};
 
 use crate::{
-    app_state::{config::get_app_key, AppState},
-    middleware::{authorization, log_requests},
-    routes,
+    app_state::{
+        config::{get_app_key, get_app_url, get_token_lifetimes},
+        AppState,
+    },
+    mail,
+    middleware::{
+        authorization, log_requests, require_permission, require_verified_email, PermissionGuard,
+    },
+    model::permission_catalogue::{self, PermissionName},
+    routes::{self, import::MAX_IMPORT_SIZE},
 };
-use axum::{middleware, routing, Router};
+use axum::{extract::DefaultBodyLimit, middleware, routing, Router};
 use sqlx::PgPool;
 use tracing_appender::rolling;
 use tracing_subscriber::layer::SubscriberExt;
@@ -23,19 +30,235 @@ pub async fn initialize_app() -> Router {
         .await
         .expect("Failed to connect to the database");
 
+    // Bring the permissions table in line with the catalogue
+    match permission_catalogue::sync(&db_pool).await {
+        Ok(report) => {
+            if !report.inserted.is_empty() {
+                tracing::info!("Added permissions: {}", report.inserted.join(", "));
+            }
+            if !report.created_roles.is_empty() {
+                tracing::info!("Created default roles: {}", report.created_roles.join(", "));
+            }
+            if !report.orphaned.is_empty() {
+                tracing::warn!(
+                    "Permissions not declared in the catalogue: {}",
+                    report.orphaned.join(", ")
+                );
+            }
+        }
+        Err(err) => tracing::error!("Failed to sync permissions: {}", err),
+    }
+
     // Create Shared Application State
-    let app_state = Arc::new(AppState { db_pool, app_key });
+    let app_state = Arc::new(AppState {
+        db_pool,
+        app_key,
+        app_url: get_app_url(),
+        token_lifetimes: get_token_lifetimes(),
+        mailer: mail::from_env().expect("Invalid mail configuration"),
+    });
 
     // Define public (unauthenticated) routes
-    let public_routes = Router::new().route("/login", routing::post(routes::auth::login));
+    let public_routes = Router::new()
+        .route("/login", routing::post(routes::auth::login))
+        .route("/refresh", routing::post(routes::auth::refresh))
+        .route("/password/forgot", routing::post(routes::password::forgot))
+        .route("/password/reset", routing::post(routes::password::reset))
+        .route("/email/verify", routing::get(routes::email::verify));
+
+    // Require a permission on a single route; runs after `authorization`
+    let can = |permission: PermissionName| {
+        middleware::from_fn_with_state(
+            PermissionGuard::new(app_state.clone(), permission),
+            require_permission,
+        )
+    };
+
+    // Define protected (authenticated) routes, which need a verified email
+    let verified_routes = Router::new()
+        .route(
+            "/users",
+            routing::get(routes::users::list).route_layer(can(PermissionName::UsersView)),
+        )
+        .route(
+            "/users/:id",
+            routing::get(routes::users::get).route_layer(can(PermissionName::UsersView)),
+        )
+        .route(
+            "/users",
+            routing::post(routes::users::create).route_layer(can(PermissionName::UsersCreate)),
+        )
+        .route(
+            "/users/batch",
+            routing::post(routes::users::batch).route_layer(can(PermissionName::UsersCreate)),
+        )
+        .route(
+            "/users/:id/roles",
+            routing::get(routes::users::list_roles).route_layer(can(PermissionName::RolesView)),
+        )
+        .route(
+            "/users/:id/roles/:role_id",
+            routing::post(routes::users::assign_role).route_layer(can(PermissionName::RolesAssign)),
+        )
+        .route(
+            "/users/:id/roles/:role_id",
+            routing::delete(routes::users::revoke_role)
+                .route_layer(can(PermissionName::RolesAssign)),
+        )
+        .route(
+            "/users/:id",
+            routing::patch(routes::users::patch).route_layer(can(PermissionName::UsersUpdate)),
+        )
+        .route(
+            "/users/:id",
+            routing::put(routes::users::update).route_layer(can(PermissionName::UsersUpdate)),
+        )
+        .route(
+            "/users/:id",
+            routing::delete(routes::users::delete).route_layer(can(PermissionName::UsersDelete)),
+        )
+        .route(
+            "/customers",
+            routing::get(routes::customers::list).route_layer(can(PermissionName::CustomersView)),
+        )
+        .route(
+            "/customers",
+            routing::post(routes::customers::create)
+                .route_layer(can(PermissionName::CustomersCreate)),
+        )
+        .route(
+            "/customers/import",
+            routing::post(routes::customers::import)
+                .route_layer(can(PermissionName::CustomersCreate))
+                .layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route(
+            "/customers/:id",
+            routing::get(routes::customers::get).route_layer(can(PermissionName::CustomersView)),
+        )
+        .route(
+            "/customers/:id",
+            routing::put(routes::customers::update)
+                .route_layer(can(PermissionName::CustomersUpdate)),
+        )
+        .route(
+            "/customers/:id",
+            routing::patch(routes::customers::patch)
+                .route_layer(can(PermissionName::CustomersUpdate)),
+        )
+        .route(
+            "/customers/:id",
+            routing::delete(routes::customers::delete)
+                .route_layer(can(PermissionName::CustomersDelete)),
+        )
+        .route(
+            "/customers/:id/restore",
+            routing::post(routes::customers::restore)
+                .route_layer(can(PermissionName::CustomersDelete)),
+        )
+        .route(
+            "/customers/:id/purge",
+            routing::delete(routes::customers::purge)
+                .route_layer(can(PermissionName::CustomersPurge)),
+        )
+        .route(
+            "/customers/:id/preferred-contact",
+            routing::put(routes::customers::set_preferred_contact)
+                .route_layer(can(PermissionName::CustomersUpdate)),
+        )
+        .route(
+            "/customers/:id/contacts",
+            routing::get(routes::contacts::list).route_layer(can(PermissionName::ContactsView)),
+        )
+        .route(
+            "/customers/:id/contacts",
+            routing::post(routes::contacts::create)
+                .route_layer(can(PermissionName::ContactsCreate)),
+        )
+        .route(
+            "/contacts/import",
+            routing::post(routes::contacts::import)
+                .route_layer(can(PermissionName::ContactsCreate))
+                .layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route(
+            "/contacts/:id",
+            routing::get(routes::contacts::get).route_layer(can(PermissionName::ContactsView)),
+        )
+        .route(
+            "/contacts/:id",
+            routing::put(routes::contacts::update).route_layer(can(PermissionName::ContactsUpdate)),
+        )
+        .route(
+            "/contacts/:id",
+            routing::patch(routes::contacts::patch)
+                .route_layer(can(PermissionName::ContactsUpdate)),
+        )
+        .route(
+            "/contacts/:id",
+            routing::delete(routes::contacts::delete)
+                .route_layer(can(PermissionName::ContactsDelete)),
+        )
+        .route(
+            "/roles",
+            routing::get(routes::roles::list).route_layer(can(PermissionName::RolesView)),
+        )
+        .route(
+            "/roles",
+            routing::post(routes::roles::create).route_layer(can(PermissionName::RolesCreate)),
+        )
+        .route(
+            "/roles/:id",
+            routing::get(routes::roles::get).route_layer(can(PermissionName::RolesView)),
+        )
+        .route(
+            "/roles/:id",
+            routing::put(routes::roles::update).route_layer(can(PermissionName::RolesUpdate)),
+        )
+        .route(
+            "/roles/:id",
+            routing::patch(routes::roles::patch).route_layer(can(PermissionName::RolesUpdate)),
+        )
+        .route(
+            "/roles/:id",
+            routing::delete(routes::roles::delete).route_layer(can(PermissionName::RolesDelete)),
+        )
+        .route(
+            "/roles/:id/permissions",
+            routing::get(routes::roles::list_permissions)
+                .route_layer(can(PermissionName::RolesView)),
+        )
+        .route(
+            "/roles/:id/permissions/:permission_id",
+            routing::post(routes::roles::attach_permission)
+                .route_layer(can(PermissionName::RolesUpdate)),
+        )
+        .route(
+            "/roles/:id/permissions/:permission_id",
+            routing::delete(routes::roles::detach_permission)
+                .route_layer(can(PermissionName::RolesUpdate)),
+        )
+        .route(
+            "/permissions",
+            routing::get(routes::permissions::list)
+                .route_layer(can(PermissionName::PermissionsView)),
+        )
+        .route("/search", routing::get(routes::search::search))
+        .route(
+            "/audit-log",
+            routing::get(routes::audit_log::list).route_layer(can(PermissionName::AuditLogView)),
+        )
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_verified_email,
+        ));
 
-    // Define protected (authenticated) routes
+    // Unverified accounts can only sign out or ask for another link
     let protected_routes = Router::new()
-        .route("/users", routing::get(routes::users::list))
-        .route("/users/:id", routing::get(routes::users::get))
-        .route("/users", routing::post(routes::users::create))
-        //      .route("/users/:id", routing::put(routes::_users::update))
-        //     .route("/users/:id", routing::delete(routes::_users::delete))
+        .route("/logout", routing::post(routes::auth::logout))
+        .route("/logout-all", routing::post(routes::auth::logout_all))
+        .route("/email/resend", routing::post(routes::email::resend))
+        .merge(verified_routes)
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             authorization,
@@ -21,7 +21,7 @@ pub fn password_rules(value: &str) -> Result<(), ValidationError> {
         ));
     }
 
-    if !value.chars().any(|c| c.is_digit(10)) {
+    if !value.chars().any(|c| c.is_ascii_digit()) {
         return Err(ValidationError::new(
             "Password must contain at least one number",
         ));